@@ -0,0 +1,96 @@
+//! Authoritative MQTT pong server, with the Quick Match matchmaker alongside.
+//!
+//! Defaults to the broker in the client's settings file (and PONG_* env overrides).
+
+use clap::Parser;
+use rust_pong::{
+    cli::{self, TlsArgs, TransportArgs},
+    config,
+    network::BrokerEndpoint,
+    matchmaker,
+    server::{self, ServerConfig},
+};
+use std::thread;
+
+#[derive(Debug, Parser)]
+#[command(name = "pong-server", version, about = "Authoritative MQTT pong server")]
+struct Args {
+    /// MQTT broker host
+    #[arg(long, value_parser = cli::parse_host)]
+    host: Option<String>,
+
+    /// MQTT broker port
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Broker username
+    #[arg(long)]
+    user: Option<String>,
+
+    /// Name of an environment variable holding the broker password
+    #[arg(long, value_name = "VAR")]
+    password_env: Option<String>,
+
+    #[command(flatten)]
+    transport: TransportArgs,
+
+    #[command(flatten)]
+    tls: TlsArgs,
+
+    /// Quick Match: only pair players whose ratings fall in the same bucket of
+    /// this many points (the reach widens the longer they wait)
+    #[arg(long, value_name = "POINTS")]
+    skill_bucket: Option<u32>,
+
+    /// Let paddle movement put spin on the ball
+    #[arg(long)]
+    spin: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let mut settings = config::load().unwrap_or_else(|e| {
+        eprintln!("pong-server: ignoring unreadable settings file ({e})");
+        config::Config::default()
+    });
+    args.transport.apply(&mut settings.broker);
+    args.tls.apply(&mut settings.broker.tls);
+    let broker = settings.broker;
+
+    let mut config = ServerConfig {
+        broker_host: args.host.unwrap_or(broker.host),
+        broker_port: args.port.unwrap_or(broker.port),
+        transport: broker.transport,
+        ws_path: broker.ws_path,
+        username: args.user.or(broker.username),
+        password: broker.password,
+        tls: broker.tls,
+        spin: args.spin,
+    };
+    if let Some(var) = args.password_env {
+        match std::env::var(&var) {
+            Ok(password) => config.password = Some(password),
+            Err(_) => {
+                eprintln!("pong-server: --password-env: environment variable {var} is not set");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let endpoint = BrokerEndpoint {
+        host: config.broker_host.clone(),
+        port: config.broker_port,
+        transport: config.transport,
+        ws_path: config.ws_path.clone(),
+        tls: config.tls.enabled,
+    };
+    println!("pong-server: serving pong/game/+/* and pong/match/queue/+ via {endpoint}");
+    let matchmaker_config = config.clone();
+    let skill_bucket = args.skill_bucket;
+    thread::spawn(move || matchmaker::run(matchmaker_config, skill_bucket));
+    if let Err(e) = server::run(config) {
+        eprintln!("pong-server: TLS setup failed: {e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,108 @@
+use rumqttd::{Broker, Config, ConnectionSettings, RouterConfig, ServerSettings};
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::matchmaker;
+use crate::network::Transport;
+use crate::server::{self, ServerConfig};
+use crate::tls::TlsSettings;
+
+/// Port the "Host Game" lobby suggests by default.
+pub const DEFAULT_HOST_PORT: u16 = 1883;
+
+/// How long `host` waits for the embedded broker to start accepting connections.
+const BROKER_STARTUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+// ---------------------------------------------------------------------------
+// Embedded broker + authoritative server + matchmaker (LAN / offline play)
+// ---------------------------------------------------------------------------
+
+/// Start an MQTT broker on `0.0.0.0:port` plus the game server and matchmaker, all inside
+/// this process. Returns once the broker accepts connections, so the caller can
+/// `network::connect` to `127.0.0.1:port` straight away. They run until the process exits.
+/// `spin` turns on paddle spin for games on this server.
+pub fn host(port: u16, spin: bool) -> io::Result<()> {
+    // Fail fast (with a useful error) if something already owns the port
+    drop(TcpListener::bind(("0.0.0.0", port))?);
+
+    let config = broker_config(port);
+    thread::spawn(move || {
+        let mut broker = Broker::new(config);
+        broker.start().ok();
+    });
+
+    let local = SocketAddr::from(([127, 0, 0, 1], port));
+    let started = Instant::now();
+    while TcpStream::connect_timeout(&local, Duration::from_millis(100)).is_err() {
+        if started.elapsed() > BROKER_STARTUP_TIMEOUT {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "embedded broker did not start",
+            ));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    let local = ServerConfig {
+        broker_host: "127.0.0.1".to_string(),
+        broker_port: port,
+        transport: Transport::Tcp,
+        ws_path: String::new(),
+        username: None,
+        password: None,
+        tls: TlsSettings::default(),
+        spin,
+    };
+    let matchmaker_config = local.clone();
+    thread::spawn(move || server::run(local));
+    // Plain first come, first served: LAN players have no ratings to bucket by
+    thread::spawn(move || matchmaker::run(matchmaker_config, None));
+
+    Ok(())
+}
+
+/// Best-guess LAN address to share with the other player.
+/// No packets are sent: connecting a UDP socket only selects the outbound interface.
+pub fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).ok()?;
+    socket.connect(("8.8.8.8", 80)).ok()?;
+    socket.local_addr().ok().map(|a| a.ip())
+}
+
+fn broker_config(port: u16) -> Config {
+    let connections = ConnectionSettings {
+        connection_timeout_ms: 60_000,
+        max_payload_size: 20 * 1024,
+        max_inflight_count: 100,
+        auth: None,
+        external_auth: None,
+        dynamic_filters: true,
+    };
+
+    let mut v4 = HashMap::new();
+    v4.insert(
+        "lan".to_string(),
+        ServerSettings {
+            name: "lan".to_string(),
+            listen: SocketAddr::from(([0, 0, 0, 0], port)),
+            tls: None,
+            next_connection_delay_ms: 1,
+            connections,
+        },
+    );
+
+    Config {
+        router: RouterConfig {
+            max_connections: 64,
+            max_outgoing_packet_count: 200,
+            max_segment_size: 1024 * 1024,
+            max_segment_count: 10,
+            ..RouterConfig::default()
+        },
+        v4: Some(v4),
+        ..Config::default()
+    }
+}
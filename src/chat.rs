@@ -0,0 +1,392 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::game_theme::ThemeColors;
+use crate::network::ChatMsg;
+
+// ---------------------------------------------------------------------------
+// In-game chat (network games)
+//
+// T opens the input line, Enter sends, Esc closes. With the input closed,
+// 1-3 send the quick-chat presets. Any of those keys bound to a paddle in
+// `[keys]` stays with the game. Messages come back through the broker like
+// everyone else's, so what shows up is what the others saw.
+// ---------------------------------------------------------------------------
+
+pub const QUICK_CHAT: [&str; 3] = ["gg", "nice shot", "rematch?"];
+pub const MAX_MESSAGE_CHARS: usize = 80;
+const HISTORY_LEN: usize = 50;
+/// Each client may send this many messages per window; more from anyone are dropped
+const RATE_LIMIT_COUNT: usize = 3;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(5);
+/// Incoming messages from all senders together per window. Session tags are
+/// the sender's to pick, so this is what holds when someone keeps changing theirs.
+const RECEIVE_LIMIT_COUNT: usize = 10;
+/// With the input closed, messages stay on screen this long
+const RECENT_FOR: Duration = Duration::from_secs(8);
+const PANE_WIDTH: u16 = 44;
+const PANE_LINES: usize = 6;
+
+/// Sliding-window limiter: at most `RATE_LIMIT_COUNT` hits per `RATE_LIMIT_WINDOW`
+#[derive(Debug, Default)]
+struct RateLimiter {
+    hits: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    fn allow(&mut self, now: Instant) -> bool {
+        self.allow_up_to(now, RATE_LIMIT_COUNT)
+    }
+
+    fn allow_up_to(&mut self, now: Instant, limit: usize) -> bool {
+        while self
+            .hits
+            .front()
+            .is_some_and(|&t| now.duration_since(t) >= RATE_LIMIT_WINDOW)
+        {
+            self.hits.pop_front();
+        }
+        if self.hits.len() >= limit {
+            return false;
+        }
+        self.hits.push_back(now);
+        true
+    }
+
+    /// No hits left in the window, so it's as good as a new one
+    fn is_idle(&self, now: Instant) -> bool {
+        self.hits
+            .back()
+            .is_none_or(|&t| now.duration_since(t) >= RATE_LIMIT_WINDOW)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ChatLine {
+    /// None for notices from the pane itself
+    from: Option<String>,
+    text: String,
+    at: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct ChatPane {
+    history: VecDeque<ChatLine>,
+    input: String,
+    open: bool,
+    sent: RateLimiter,
+    /// Incoming limits, by sender session; idle ones are dropped
+    received: HashMap<String, RateLimiter>,
+    /// Incoming limit across all senders
+    received_all: RateLimiter,
+    outbox: Vec<String>,
+}
+
+/// Printable characters only, at most `MAX_MESSAGE_CHARS`, trimmed
+pub fn clean_message(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_MESSAGE_CHARS)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+impl ChatPane {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// A message from the chat topic (our own included)
+    pub fn receive(&mut self, msg: &ChatMsg) {
+        self.receive_at(msg, Instant::now());
+    }
+
+    fn receive_at(&mut self, msg: &ChatMsg, now: Instant) {
+        self.received.retain(|_, limiter| !limiter.is_idle(now));
+        // The sender's own limit first, so one flooder doesn't use up the shared
+        // one; new senders are only tracked once the shared limit lets them in
+        let allowed = match self.received.get_mut(&msg.client) {
+            Some(limiter) => {
+                limiter.allow(now) && self.received_all.allow_up_to(now, RECEIVE_LIMIT_COUNT)
+            }
+            None if self.received_all.allow_up_to(now, RECEIVE_LIMIT_COUNT) => {
+                let limiter = self.received.entry(msg.client.clone()).or_default();
+                limiter.allow(now)
+            }
+            None => false,
+        };
+        if !allowed {
+            return;
+        }
+        let text = clean_message(&msg.text);
+        if text.is_empty() {
+            return;
+        }
+        let from = clean_message(&msg.name);
+        self.push(Some(if from.is_empty() { "?".to_string() } else { from }), text);
+    }
+
+    fn push(&mut self, from: Option<String>, text: String) {
+        self.history.push_back(ChatLine {
+            from,
+            text,
+            at: Instant::now(),
+        });
+        while self.history.len() > HISTORY_LEN {
+            self.history.pop_front();
+        }
+    }
+
+    fn send(&mut self, text: &str) {
+        let text = clean_message(text);
+        if text.is_empty() {
+            return;
+        }
+        if self.sent.allow(Instant::now()) {
+            self.outbox.push(text);
+        } else {
+            self.push(None, "Slow down - 3 messages per 5 seconds".to_string());
+        }
+    }
+
+    /// Messages to publish, oldest first
+    pub fn take_outbox(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    /// Returns true if the chat used the key. With the input closed, keys in
+    /// `bound` (the game's own bindings) are left alone.
+    pub fn handle_key(&mut self, code: KeyCode, bound: &[KeyCode]) -> bool {
+        if !self.open {
+            let lower = match code {
+                KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+                other => other,
+            };
+            if bound.contains(&lower) {
+                return false;
+            }
+            return match code {
+                KeyCode::Char('t') | KeyCode::Char('T') => {
+                    self.open = true;
+                    true
+                }
+                KeyCode::Char(c @ '1'..='3') => {
+                    self.send(QUICK_CHAT[c as usize - '1' as usize]);
+                    true
+                }
+                _ => false,
+            };
+        }
+        match code {
+            KeyCode::Esc => self.open = false,
+            KeyCode::Enter => {
+                let text = std::mem::take(&mut self.input);
+                self.send(&text);
+                self.open = false;
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) if self.input.chars().count() < MAX_MESSAGE_CHARS => {
+                self.input.push(c);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Draw in the bottom-left corner of `area` (the court block, border included):
+    /// history and input while open, otherwise just the last few seconds' messages.
+    pub fn draw(&self, frame: &mut Frame, area: Rect, colors: &ThemeColors) {
+        let now = Instant::now();
+        let shown: Vec<&ChatLine> = if self.open {
+            self.history.iter().collect()
+        } else {
+            self.history
+                .iter()
+                .filter(|line| now.duration_since(line.at) < RECENT_FOR)
+                .collect()
+        };
+        if shown.is_empty() && !self.open {
+            return;
+        }
+
+        let mut lines: Vec<String> = shown
+            .iter()
+            .rev()
+            .take(PANE_LINES)
+            .rev()
+            .map(|line| match &line.from {
+                Some(from) => format!("{}: {}", from, line.text),
+                None => format!("* {}", line.text),
+            })
+            .collect();
+        if self.open {
+            lines.push(format!("> {}_", self.input));
+        }
+
+        let width = PANE_WIDTH.min(area.width.saturating_sub(2));
+        let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+        if width < 4 || height < 3 {
+            return;
+        }
+        let pane_area = Rect::new(
+            area.x + 1,
+            area.bottom().saturating_sub(height + 1),
+            width,
+            height,
+        );
+        let title = if self.open {
+            " Chat - Enter send, Esc close "
+        } else {
+            " Chat (T) "
+        };
+        // Keep the newest lines (and the input) in view when long ones wrap
+        let inner_width = width.saturating_sub(2).max(1) as usize;
+        let wrapped: u16 = lines
+            .iter()
+            .map(|l| l.chars().count().max(1).div_ceil(inner_width) as u16)
+            .sum();
+        let pane = Paragraph::new(lines.join("\n"))
+            .wrap(Wrap { trim: false })
+            .scroll((wrapped.saturating_sub(height - 2), 0))
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .style(Style::default().fg(colors.accent).bg(colors.background)),
+            )
+            .style(Style::default().fg(colors.text));
+        frame.render_widget(Clear, pane_area);
+        frame.render_widget(pane, pane_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn rate_limiter_allows_three_per_window() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::default();
+        for _ in 0..RATE_LIMIT_COUNT {
+            assert!(limiter.allow(start));
+        }
+        assert!(!limiter.allow(start + secs(4)));
+        // Refused attempts don't count against the window
+        assert!(limiter.allow(start + RATE_LIMIT_WINDOW));
+    }
+
+    #[test]
+    fn rate_limiter_window_slides() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::default();
+        assert!(limiter.allow(start));
+        assert!(limiter.allow(start + secs(2)));
+        assert!(limiter.allow(start + secs(3)));
+        assert!(!limiter.allow(start + secs(4)));
+        // Only the first hit has left the window
+        assert!(limiter.allow(start + secs(5)));
+        assert!(!limiter.allow(start + secs(6)));
+        assert!(limiter.allow(start + secs(7)));
+    }
+
+    #[test]
+    fn typed_message_is_sent_on_enter() {
+        let mut pane = ChatPane::default();
+        assert!(pane.handle_key(KeyCode::Char('t'), &[]));
+        assert!(pane.is_open());
+        for c in "hi w".chars() {
+            pane.handle_key(KeyCode::Char(c), &[]);
+        }
+        pane.handle_key(KeyCode::Backspace, &[]);
+        pane.handle_key(KeyCode::Enter, &[]);
+        assert!(!pane.is_open());
+        assert_eq!(pane.take_outbox(), vec!["hi".to_string()]);
+    }
+
+    #[test]
+    fn quick_chat_keys_send_presets() {
+        let mut pane = ChatPane::default();
+        assert!(pane.handle_key(KeyCode::Char('2'), &[]));
+        assert_eq!(pane.take_outbox(), vec![QUICK_CHAT[1].to_string()]);
+        assert!(!pane.handle_key(KeyCode::Char('4'), &[]));
+    }
+
+    #[test]
+    fn bound_keys_stay_with_the_game() {
+        let mut pane = ChatPane::default();
+        let bound = [KeyCode::Char('t'), KeyCode::Char('1')];
+        assert!(!pane.handle_key(KeyCode::Char('t'), &bound));
+        assert!(!pane.handle_key(KeyCode::Char('T'), &bound));
+        assert!(!pane.handle_key(KeyCode::Char('1'), &bound));
+        assert!(pane.take_outbox().is_empty());
+        // Unbound presets still work, and typing isn't affected once open
+        assert!(pane.handle_key(KeyCode::Char('3'), &bound));
+        pane.open = true;
+        assert!(pane.handle_key(KeyCode::Char('t'), &bound));
+        assert_eq!(pane.input, "t");
+    }
+
+    #[test]
+    fn sending_too_fast_shows_a_notice() {
+        let mut pane = ChatPane::default();
+        for _ in 0..RATE_LIMIT_COUNT + 1 {
+            pane.handle_key(KeyCode::Char('1'), &[]);
+        }
+        assert_eq!(pane.take_outbox().len(), RATE_LIMIT_COUNT);
+        assert!(pane.history.back().is_some_and(|line| line.from.is_none()));
+    }
+
+    fn chat(client: &str, text: &str) -> ChatMsg {
+        ChatMsg {
+            client: client.to_string(),
+            name: client.to_string(),
+            player: None,
+            text: text.to_string(),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn incoming_messages_are_limited_per_sender() {
+        let start = Instant::now();
+        let mut pane = ChatPane::default();
+        for _ in 0..RATE_LIMIT_COUNT + 2 {
+            pane.receive_at(&chat("flood", "spam"), start);
+        }
+        pane.receive_at(&chat("alice", "gg"), start);
+        assert_eq!(pane.history.len(), RATE_LIMIT_COUNT + 1);
+        assert_eq!(pane.history.back().unwrap().text, "gg");
+    }
+
+    #[test]
+    fn changing_session_tags_doesnt_beat_the_shared_limit() {
+        let start = Instant::now();
+        let mut pane = ChatPane::default();
+        for i in 0..100 {
+            pane.receive_at(&chat(&format!("spammer-{i}"), "spam"), start);
+        }
+        assert_eq!(pane.history.len(), RECEIVE_LIMIT_COUNT);
+        assert_eq!(pane.received.len(), RECEIVE_LIMIT_COUNT);
+
+        // Once the window has passed the idle limiters are forgotten
+        pane.receive_at(&chat("alice", "gg"), start + RATE_LIMIT_WINDOW);
+        assert_eq!(pane.history.back().unwrap().text, "gg");
+        assert_eq!(pane.received.len(), 1);
+    }
+}
@@ -0,0 +1,216 @@
+use clap::{Args, Parser};
+use std::io;
+use std::path::PathBuf;
+
+use crate::{
+    config::{BrokerSettings, Config},
+    game_theme::GameTheme,
+    network::{BrokerEndpoint, Transport},
+    tls::TlsSettings,
+};
+
+// ---------------------------------------------------------------------------
+// Client command line. Flags override the settings file for this run only.
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Parser)]
+#[command(name = "rust-pong", version, about = "Terminal pong over MQTT")]
+pub struct Cli {
+    /// MQTT broker host; joins a game straight away, skipping the menu
+    #[arg(long, value_parser = parse_host)]
+    pub host: Option<String>,
+
+    /// MQTT broker port; joins a game straight away
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Game ID to join; both players must use the same one
+    #[arg(long, value_name = "ID")]
+    pub game_id: Option<String>,
+
+    /// Player slot to take (1 = left, 2 = right); joins a game straight away
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=2))]
+    pub player: Option<u8>,
+
+    /// Profile name shown to your opponent
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Record online games to the replays directory
+    #[arg(long)]
+    pub record: bool,
+
+    /// Color theme (monokai, solarized, dracula, gruvbox_dark, nord, one_dark, high_contrast)
+    #[arg(long, value_parser = parse_theme)]
+    pub theme: Option<GameTheme>,
+
+    /// Broker username
+    #[arg(long)]
+    pub user: Option<String>,
+
+    /// Name of an environment variable holding the broker password
+    #[arg(long, value_name = "VAR")]
+    pub password_env: Option<String>,
+
+    #[command(flatten)]
+    pub transport: TransportArgs,
+
+    #[command(flatten)]
+    pub tls: TlsArgs,
+
+    /// Run the AI-vs-AI demo (for kiosks) instead of the menu
+    #[arg(long, conflicts_with_all = ["host", "port", "game_id", "player"])]
+    pub screensaver: bool,
+}
+
+/// Transport flags, shared with the pong-server binary.
+#[derive(Debug, Default, Args)]
+pub struct TransportArgs {
+    /// How to reach the broker: tcp, or websocket (MQTT over HTTP(S), e.g. port 443)
+    #[arg(long, value_parser = parse_transport)]
+    pub transport: Option<Transport>,
+
+    /// WebSocket endpoint path on the broker [default: /mqtt]
+    #[arg(long, value_name = "PATH")]
+    pub ws_path: Option<String>,
+}
+
+/// `--host` takes a bare host name; the port, transport and TLS have flags of
+/// their own, so a URL or `host:port` is refused instead of half-used.
+pub fn parse_host(host: &str) -> Result<String, String> {
+    let endpoint = BrokerEndpoint::parse(host, 0)?;
+    if endpoint.host != host.trim() {
+        return Err(format!(
+            "'{}' is not a host name (use --port, --transport and --tls)",
+            host
+        ));
+    }
+    Ok(endpoint.host)
+}
+
+fn parse_transport(name: &str) -> Result<Transport, String> {
+    Transport::from_name(name).ok_or_else(|| format!("unknown transport '{}' (tcp, websocket)", name))
+}
+
+impl TransportArgs {
+    pub fn apply(&self, broker: &mut BrokerSettings) {
+        if let Some(transport) = self.transport {
+            broker.transport = transport;
+        }
+        if let Some(path) = &self.ws_path {
+            broker.ws_path = path.clone();
+        }
+    }
+}
+
+/// TLS flags, shared with the pong-server binary.
+/// Giving any certificate flag implies --tls.
+#[derive(Debug, Default, Args)]
+pub struct TlsArgs {
+    /// Connect to the broker over TLS (usually port 8883)
+    #[arg(long)]
+    pub tls: bool,
+
+    /// PEM CA bundle to trust instead of the system roots
+    #[arg(long, value_name = "PATH")]
+    pub ca_file: Option<PathBuf>,
+
+    /// PEM client certificate for mutual TLS (needs --client-key)
+    #[arg(long, value_name = "PATH", requires = "client_key")]
+    pub client_cert: Option<PathBuf>,
+
+    /// PEM private key for --client-cert
+    #[arg(long, value_name = "PATH", requires = "client_cert")]
+    pub client_key: Option<PathBuf>,
+
+    /// Name the broker certificate must match, when it differs from --host
+    /// (checked only; the handshake still sends --host as SNI)
+    #[arg(long, value_name = "NAME")]
+    pub tls_verify_name: Option<String>,
+
+    /// Accept any broker certificate (development only)
+    #[arg(long)]
+    pub insecure_skip_verify: bool,
+}
+
+impl TlsArgs {
+    pub fn apply(&self, tls: &mut TlsSettings) {
+        if let Some(path) = &self.ca_file {
+            tls.ca_file = Some(path.clone());
+        }
+        if let (Some(cert), Some(key)) = (&self.client_cert, &self.client_key) {
+            tls.client_cert = Some(cert.clone());
+            tls.client_key = Some(key.clone());
+        }
+        if let Some(name) = &self.tls_verify_name {
+            tls.verify_name = Some(name.clone());
+        }
+        if self.insecure_skip_verify {
+            tls.insecure_skip_verify = true;
+        }
+        if self.tls
+            || self.ca_file.is_some()
+            || self.client_cert.is_some()
+            || self.tls_verify_name.is_some()
+            || self.insecure_skip_verify
+        {
+            tls.enabled = true;
+        }
+    }
+}
+
+fn parse_theme(name: &str) -> Result<GameTheme, String> {
+    GameTheme::from_name(name).ok_or_else(|| format!("unknown theme '{}'", name))
+}
+
+impl Cli {
+    /// True when any connection flag was given, i.e. skip the menu and lobby.
+    pub fn wants_network_game(&self) -> bool {
+        self.host.is_some()
+            || self.port.is_some()
+            || self.game_id.is_some()
+            || self.player.is_some()
+            || self.user.is_some()
+            || self.password_env.is_some()
+    }
+
+    /// Layer the flags on top of the loaded settings.
+    pub fn apply(&self, config: &mut Config) -> io::Result<()> {
+        if let Some(host) = &self.host {
+            config.broker.host = host.clone();
+        }
+        if let Some(port) = self.port {
+            config.broker.port = port;
+        }
+        if let Some(user) = &self.user {
+            config.broker.username = Some(user.clone());
+        }
+        if let Some(var) = &self.password_env {
+            let password = std::env::var(var).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("--password-env: environment variable {} is not set", var),
+                )
+            })?;
+            config.broker.password = Some(password);
+        }
+        if let Some(game_id) = &self.game_id {
+            config.game.game_id = game_id.clone();
+        }
+        if let Some(player) = self.player {
+            config.game.player = player;
+        }
+        if let Some(name) = &self.name {
+            config.game.name = name.clone();
+        }
+        if let Some(theme) = self.theme {
+            config.game.theme = theme;
+        }
+        if self.record {
+            config.game.record = true;
+        }
+        self.transport.apply(&mut config.broker);
+        self.tls.apply(&mut config.broker.tls);
+        Ok(())
+    }
+}
@@ -0,0 +1,459 @@
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+use crate::{
+    game::PLAYER_NAME_CHAR_LEN,
+    game_theme::GameTheme,
+    lobby,
+    network::{BrokerEndpoint, NetworkConfig, Transport},
+    sim::SpeedRamp,
+    smoothing::SmoothingSettings,
+    tls::TlsSettings,
+};
+
+// ---------------------------------------------------------------------------
+// Settings file: <config dir>/rust-pong/config.toml
+//
+//   Linux:   ~/.config/rust-pong/config.toml
+//   macOS:   ~/Library/Application Support/rust-pong/config.toml
+//   Windows: %APPDATA%\rust-pong\config.toml
+//
+// Every field is optional; missing ones fall back to the built-in defaults.
+// PONG_* environment variables override the file for the current run only and
+// are never written back by `update`.
+// ---------------------------------------------------------------------------
+
+const CONFIG_DIR_NAME: &str = "rust-pong";
+const CONFIG_FILE_NAME: &str = "config.toml";
+/// Overrides the config file location entirely
+const CONFIG_PATH_ENV: &str = "PONG_CONFIG";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub broker: BrokerSettings,
+    pub game: GameSettings,
+    pub keys: KeyBindingNames,
+    /// How network games draw the server's ball between packets
+    pub smoothing: SmoothingSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrokerSettings {
+    pub host: String,
+    pub port: u16,
+    /// "tcp" or "websocket" (MQTT over HTTP(S), for networks that only allow 80/443)
+    pub transport: Transport,
+    /// WebSocket endpoint path on the broker
+    pub ws_path: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// `[broker.tls]`; plain TCP unless `enabled = true`
+    pub tls: TlsSettings,
+}
+
+impl Default for BrokerSettings {
+    fn default() -> Self {
+        let net = NetworkConfig::default();
+        Self {
+            host: net.broker_host,
+            port: net.broker_port,
+            transport: net.transport,
+            ws_path: net.ws_path,
+            username: net.username,
+            password: net.password,
+            tls: net.tls,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    /// Pre-filled Game ID in the online lobby
+    pub game_id: String,
+    /// Pre-selected player slot in the online lobby (1 or 2)
+    pub player: u8,
+    /// Profile name shown to opponents and in the lobby browser; empty = OS user name
+    pub name: String,
+    pub theme: GameTheme,
+    /// CPU difficulty for "Play vs CPU", 0.0 - 2.0
+    pub difficulty: f32,
+    /// Rating sent with Quick Match; matchmakers with skill buckets pair similar ratings
+    pub skill: Option<u32>,
+    /// Save every online game to the replays directory
+    pub record: bool,
+    /// Local modes: a moving paddle puts spin on the ball
+    pub spin: bool,
+    /// How the ball speeds up over a rally, in local games and online games you open
+    pub ramp: SpeedRamp,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            game_id: "demo".to_string(),
+            player: 1,
+            name: String::new(),
+            theme: GameTheme::Monokai,
+            difficulty: 1.0,
+            skill: None,
+            record: false,
+            spin: false,
+            ramp: SpeedRamp::default(),
+        }
+    }
+}
+
+/// Key names as written in the file: "Up", "Down", "Left", "Right", "Space",
+/// "Enter", "Tab", or a single character such as "w" or "/".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindingNames {
+    pub p1_up: String,
+    pub p1_down: String,
+    pub p1_power: String,
+    pub p2_up: String,
+    pub p2_down: String,
+    pub p2_power: String,
+}
+
+impl Default for KeyBindingNames {
+    fn default() -> Self {
+        Self {
+            p1_up: "Up".to_string(),
+            p1_down: "Down".to_string(),
+            p1_power: "/".to_string(),
+            p2_up: "w".to_string(),
+            p2_down: "s".to_string(),
+            p2_power: "Space".to_string(),
+        }
+    }
+}
+
+/// Resolved key bindings used by `Game::handle_key_event`.
+/// In single-human modes (online, vs CPU) both sets move the one local paddle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyBindings {
+    pub p1_up: KeyCode,
+    pub p1_down: KeyCode,
+    pub p1_power: KeyCode,
+    pub p2_up: KeyCode,
+    pub p2_down: KeyCode,
+    pub p2_power: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindingNames::default().resolve()
+    }
+}
+
+impl KeyBindingNames {
+    /// Unknown key names fall back to the default binding for that action.
+    pub fn resolve(&self) -> KeyBindings {
+        let defaults = KeyBindingNames::default();
+        let key = |name: &str, fallback: &str| {
+            parse_key(name)
+                .or_else(|| parse_key(fallback))
+                .unwrap_or(KeyCode::Null)
+        };
+        KeyBindings {
+            p1_up: key(&self.p1_up, &defaults.p1_up),
+            p1_down: key(&self.p1_down, &defaults.p1_down),
+            p1_power: key(&self.p1_power, &defaults.p1_power),
+            p2_up: key(&self.p2_up, &defaults.p2_up),
+            p2_down: key(&self.p2_down, &defaults.p2_down),
+            p2_power: key(&self.p2_power, &defaults.p2_power),
+        }
+    }
+}
+
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c.to_ascii_lowercase()));
+    }
+    match name.trim().to_ascii_lowercase().as_str() {
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "space" => Some(KeyCode::Char(' ')),
+        "enter" => Some(KeyCode::Enter),
+        "tab" => Some(KeyCode::Tab),
+        _ => None,
+    }
+}
+
+/// Short label for HUD/help text, e.g. "↑", "W", "Space".
+pub fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_ascii_uppercase().to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Load / save
+// ---------------------------------------------------------------------------
+
+pub fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(CONFIG_PATH_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// Read the settings file as-is (no environment overrides).
+/// A missing file is not an error and yields the defaults.
+pub fn load_file() -> io::Result<Config> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e.message()),
+            )
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Settings for this run: the file, then PONG_* environment variables on top.
+pub fn load() -> io::Result<Config> {
+    let mut config = load_file()?;
+    config.apply_env();
+    Ok(config)
+}
+
+/// Persist a change made in a menu. Re-reads the file so environment overrides
+/// from this run are never written to disk; errors are ignored like other
+/// best-effort I/O in the client.
+pub fn update(change: impl FnOnce(&mut Config)) {
+    let Ok(mut config) = load_file() else {
+        // Don't clobber a file the user is halfway through hand-editing
+        return;
+    };
+    change(&mut config);
+    save(&config).ok();
+}
+
+/// Write the settings that differ from the built-in defaults. Defaults are
+/// left out so the file never picks up the default broker credentials and
+/// later changes to the defaults still reach existing users.
+pub fn save(config: &Config) -> io::Result<()> {
+    let path = config_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let invalid = |e: toml::ser::Error| io::Error::new(io::ErrorKind::InvalidData, e.to_string());
+    let mut table = toml::Table::try_from(config).map_err(invalid)?;
+    let defaults = toml::Table::try_from(Config::default()).map_err(invalid)?;
+    strip_defaults(&mut table, &defaults);
+    let text = toml::to_string_pretty(&table).map_err(invalid)?;
+    fs::write(path, text)
+}
+
+/// Remove every value that equals its default, and tables left empty by that
+fn strip_defaults(table: &mut toml::Table, defaults: &toml::Table) {
+    table.retain(|key, value| match (value, defaults.get(key)) {
+        (toml::Value::Table(inner), Some(toml::Value::Table(default))) => {
+            strip_defaults(inner, default);
+            !inner.is_empty()
+        }
+        (value, default) => Some(&*value) != default,
+    });
+}
+
+impl Config {
+    fn apply_env(&mut self) {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+        if let Some(host) = var("PONG_BROKER_HOST") {
+            self.broker.host = host;
+        }
+        if let Some(port) = var("PONG_BROKER_PORT").and_then(|p| p.parse().ok()) {
+            self.broker.port = port;
+        }
+        if let Some(transport) = var("PONG_TRANSPORT").and_then(|t| Transport::from_name(&t)) {
+            self.broker.transport = transport;
+        }
+        if let Some(user) = var("PONG_USERNAME") {
+            self.broker.username = Some(user);
+        }
+        if let Some(pass) = var("PONG_PASSWORD") {
+            self.broker.password = Some(pass);
+        }
+        if let Some(game_id) = var("PONG_GAME_ID") {
+            self.game.game_id = game_id;
+        }
+        if let Some(player) = var("PONG_PLAYER").and_then(|p| p.parse().ok()) {
+            self.game.player = player;
+        }
+        if let Some(name) = var("PONG_NAME") {
+            self.game.name = name;
+        }
+        if let Some(theme) = var("PONG_THEME").and_then(|t| GameTheme::from_name(&t)) {
+            self.game.theme = theme;
+        }
+        if let Some(record) = var("PONG_RECORD") {
+            self.game.record = matches!(record.as_str(), "1" | "true" | "yes");
+        }
+        if let Some(spin) = var("PONG_SPIN") {
+            self.game.spin = matches!(spin.as_str(), "1" | "true" | "yes");
+        }
+        if let Some(difficulty) = var("PONG_DIFFICULTY").and_then(|d| d.parse().ok()) {
+            self.game.difficulty = difficulty;
+        }
+        self.sanitize();
+    }
+
+    fn sanitize(&mut self) {
+        if self.game.player != 1 && self.game.player != 2 {
+            self.game.player = 1;
+        }
+        // clamp() passes NaN through, and a NaN difficulty zeroes the CPU tick rate
+        self.game.difficulty = if self.game.difficulty.is_finite() {
+            self.game.difficulty.clamp(0.0, 2.0)
+        } else {
+            GameSettings::default().difficulty
+        };
+        self.game.ramp = self.game.ramp.sanitized();
+        self.smoothing.delay_ms = self.smoothing.delay_ms.min(1000);
+    }
+
+    /// Name to play under: the configured one, else the OS user name
+    pub fn profile_name(&self) -> String {
+        match self.game.name.trim() {
+            "" => lobby::default_player_name(),
+            name => name.chars().take(PLAYER_NAME_CHAR_LEN).collect(),
+        }
+    }
+
+    pub fn broker_endpoint(&self) -> BrokerEndpoint {
+        BrokerEndpoint {
+            host: self.broker.host.clone(),
+            port: self.broker.port,
+            transport: self.broker.transport,
+            ws_path: self.broker.ws_path.clone(),
+            tls: self.broker.tls.enabled,
+        }
+    }
+
+    /// "host:port" (or a URL for TLS / WebSocket) as shown in the lobby's Broker field
+    pub fn broker_address(&self) -> String {
+        self.broker_endpoint().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// The environment is process-wide, so tests that set PONG_* run one at a time
+    static ENV: Mutex<()> = Mutex::new(());
+
+    /// Runs `test` with the settings file at a fresh temp path holding `file`
+    /// and the given PONG_* variables set; returns the file's contents after.
+    fn with_env(name: &str, file: &str, vars: &[(&str, &str)], test: impl FnOnce()) -> String {
+        let _guard = ENV.lock().unwrap_or_else(|e| e.into_inner());
+        let path =
+            std::env::temp_dir().join(format!("pong-config-{}-{}.toml", std::process::id(), name));
+        fs::write(&path, file).unwrap();
+        std::env::set_var(CONFIG_PATH_ENV, &path);
+        for (var, value) in vars {
+            std::env::set_var(var, value);
+        }
+        test();
+        for (var, _) in vars {
+            std::env::remove_var(var);
+        }
+        std::env::remove_var(CONFIG_PATH_ENV);
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+        text
+    }
+
+    #[test]
+    fn save_writes_only_what_differs_from_the_defaults() {
+        let text = with_env("round-trip", "", &[], || {
+            let mut config = Config::default();
+            config.broker.port = 8883;
+            config.broker.tls.enabled = true;
+            config.game.theme = GameTheme::Nord;
+            save(&config).unwrap();
+
+            let loaded = load_file().unwrap();
+            assert_eq!(loaded.broker.port, 8883);
+            assert!(loaded.broker.tls.enabled);
+            assert_eq!(loaded.game.theme, GameTheme::Nord);
+            assert_eq!(loaded.broker.password, Config::default().broker.password);
+        });
+        assert!(text.contains("port = 8883"), "{}", text);
+        for default in ["host", "password", "game_id", "[keys]", "[smoothing]"] {
+            assert!(!text.contains(default), "{} in {}", default, text);
+        }
+    }
+
+    #[test]
+    fn environment_overrides_beat_the_file() {
+        let file = "[broker]\nport = 1999\n[game]\ntheme = \"dracula\"\ngame_id = \"file\"\n";
+        let vars = [("PONG_BROKER_PORT", "2000"), ("PONG_GAME_ID", "env")];
+        with_env("env", file, &vars, || {
+            let config = load().unwrap();
+            assert_eq!(config.broker.port, 2000);
+            assert_eq!(config.game.game_id, "env");
+            assert_eq!(config.game.theme, GameTheme::Dracula);
+        });
+    }
+
+    #[test]
+    fn update_never_writes_environment_values() {
+        let file = "[game]\ngame_id = \"file\"\n";
+        let vars = [("PONG_GAME_ID", "env"), ("PONG_PASSWORD", "hunter2")];
+        let text = with_env("update", file, &vars, || update(|c| c.game.record = true));
+        assert!(text.contains("game_id = \"file\""), "{}", text);
+        assert!(text.contains("record = true"), "{}", text);
+        assert!(
+            !text.contains("env") && !text.contains("hunter2"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn sanitize_clamps_out_of_range_values() {
+        let mut config = Config::default();
+        config.game.player = 3;
+        config.game.difficulty = 5.0;
+        config.smoothing.delay_ms = 60_000;
+        config.sanitize();
+        assert_eq!(config.game.player, 1);
+        assert_eq!(config.game.difficulty, 2.0);
+        assert_eq!(config.smoothing.delay_ms, 1000);
+
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            config.game.difficulty = bad;
+            config.sanitize();
+            assert_eq!(config.game.difficulty, 1.0, "{}", bad);
+        }
+        with_env("nan", "", &[("PONG_DIFFICULTY", "nan")], || {
+            assert_eq!(load().unwrap().game.difficulty, 1.0);
+        });
+    }
+}
@@ -0,0 +1,206 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::game_theme::ThemeColors;
+use crate::network::{ClockSync, TrafficCounters, TrafficSnapshot};
+
+// ---------------------------------------------------------------------------
+// Network diagnostics overlay (F3 in online games)
+//
+// Per-topic rates come from the network thread's `TrafficCounters`, sampled
+// over one-second windows. Connection status, ball arrivals and packets the
+// smoothers rejected are fed in by the game as `drain_network_events` hands
+// them over.
+// ---------------------------------------------------------------------------
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+const OVERLAY_WIDTH: u16 = 36;
+
+#[derive(Debug)]
+pub struct NetDiagnostics {
+    traffic: Arc<TrafficCounters>,
+    window_start: Instant,
+    window_base: TrafficSnapshot,
+    /// Counts over the last complete window, and its length in seconds
+    window: TrafficSnapshot,
+    window_secs: f64,
+    /// Packets dropped as out of order (older than one already drawn)
+    dropped: u64,
+    /// Local arrival time of the newest ball packet, ms
+    last_ball_ms: Option<u64>,
+    status: String,
+}
+
+impl NetDiagnostics {
+    pub fn new(traffic: Arc<TrafficCounters>) -> Self {
+        let window_base = traffic.snapshot();
+        Self {
+            traffic,
+            window_start: Instant::now(),
+            window_base,
+            window: TrafficSnapshot::default(),
+            window_secs: 1.0,
+            dropped: 0,
+            last_ball_ms: None,
+            status: "Connecting".to_string(),
+        }
+    }
+
+    pub fn set_status(&mut self, status: &str) {
+        if self.status != status {
+            self.status = status.to_string();
+        }
+    }
+
+    pub fn on_ball(&mut self, local_ms: u64) {
+        self.last_ball_ms = Some(local_ms);
+    }
+
+    pub fn on_dropped(&mut self) {
+        self.dropped += 1;
+    }
+
+    /// Close the rate window once it is a second old. Call every frame.
+    pub fn update(&mut self) {
+        let elapsed = self.window_start.elapsed();
+        if elapsed < RATE_WINDOW {
+            return;
+        }
+        let now = self.traffic.snapshot();
+        self.window = now.since(&self.window_base);
+        self.window_secs = elapsed.as_secs_f64();
+        self.window_base = now;
+        self.window_start = Instant::now();
+    }
+
+    fn rate(&self, count: u64) -> f64 {
+        count as f64 / self.window_secs
+    }
+
+    fn lines(&self, clock: Option<ClockSync>, now_ms: u64) -> Vec<String> {
+        let w = &self.window;
+        let rtt = match clock {
+            Some(c) => format!(
+                "{:.0}ms ±{:.0}  offset {:+.0}ms",
+                c.rtt_ms, c.jitter_ms, c.offset_ms
+            ),
+            None => "--".to_string(),
+        };
+        let ball_age = match self.last_ball_ms {
+            Some(t) => format!("{}ms ago", now_ms.saturating_sub(t)),
+            None => "none yet".to_string(),
+        };
+        let totals = self.traffic.snapshot();
+        vec![
+            format!("Status  {}", self.status),
+            format!("RTT     {}", rtt),
+            format!("Ball    {:>3.0}/s  last {}", self.rate(w.ball_in), ball_age),
+            format!(
+                "Paddle  {:>3.0}/s in  {:.0}/s out",
+                self.rate(w.paddle_in),
+                self.rate(w.paddle_out)
+            ),
+            format!(
+                "State   {:>3.0}/s  Pong {:.0}/s  Chat {:.0}/s",
+                self.rate(w.state_in),
+                self.rate(w.pong_in),
+                self.rate(w.chat_in)
+            ),
+            format!("Bad     {} unparsable  {} dropped", totals.unparsable, self.dropped),
+        ]
+    }
+
+    /// Draw in the top-right corner of `area` (the court block, border included).
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        colors: &ThemeColors,
+        clock: Option<ClockSync>,
+        now_ms: u64,
+    ) {
+        let lines = self.lines(clock, now_ms);
+        let width = OVERLAY_WIDTH.min(area.width.saturating_sub(2));
+        let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+        if width == 0 || height == 0 {
+            return;
+        }
+        let overlay_area = Rect::new(
+            area.right().saturating_sub(width + 1),
+            area.y + 1,
+            width,
+            height,
+        );
+        let overlay = Paragraph::new(lines.join("\n"))
+            .block(
+                Block::default()
+                    .title(" Network (F3) ")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .style(Style::default().fg(colors.accent).bg(colors.background)),
+            )
+            .style(Style::default().fg(colors.text));
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(overlay, overlay_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    fn bump(counter: &AtomicU64, by: u64) {
+        counter.fetch_add(by, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn rates_cover_the_last_window_and_totals_keep_adding_up() {
+        let traffic = Arc::new(TrafficCounters::default());
+        // Counted before the overlay opened, so not in any window
+        bump(&traffic.ball_in, 7);
+        let mut diag = NetDiagnostics::new(traffic.clone());
+
+        bump(&traffic.ball_in, 40);
+        bump(&traffic.paddle_in, 20);
+        bump(&traffic.paddle_out, 30);
+        bump(&traffic.state_in, 4);
+        bump(&traffic.pong_in, 2);
+        bump(&traffic.chat_in, 2);
+        bump(&traffic.unparsable, 3);
+        diag.on_dropped();
+        diag.on_dropped();
+        diag.on_ball(9_900);
+
+        // Too soon: the window stays open and the rates at zero
+        diag.update();
+        assert_eq!(diag.window, TrafficSnapshot::default());
+
+        // A two-second window halves the counts
+        diag.window_start -= Duration::from_secs(2);
+        diag.update();
+        assert_eq!(
+            diag.lines(None, 10_000)[2..],
+            [
+                "Ball     20/s  last 100ms ago",
+                "Paddle   10/s in  15/s out",
+                "State     2/s  Pong 1/s  Chat 1/s",
+                "Bad     3 unparsable  2 dropped",
+            ]
+        );
+
+        // The next window starts from where that one ended; totals don't reset
+        bump(&traffic.unparsable, 1);
+        diag.window_start -= Duration::from_secs(1);
+        diag.update();
+        let lines = diag.lines(None, 10_000);
+        assert_eq!(lines[2], "Ball      0/s  last 100ms ago");
+        assert_eq!(lines[5], "Bad     4 unparsable  2 dropped");
+    }
+}
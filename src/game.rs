@@ -1,7 +1,7 @@
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
-use rand::random;
 use std::{
     io,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
@@ -13,65 +13,63 @@ use ratatui::{
 };
 
 use crate::{
+    chat::ChatPane,
+    config::{key_label, KeyBindings},
+    diagnostics::NetDiagnostics,
     game_theme::GameTheme,
     helpers::{centered_rect, string_to_char_array},
+    network::{now_ms, BallMsg, ChatMsg, ClockSync, PaddleMsg, TrafficCounters},
+    replay::ReplayFrame,
+    sim::{Player, Sim, SpeedRamp, PADDLE_MAX_Y},
+    smoothing::{BallSmoother, PaddleSmoother, SmoothingSettings},
 };
 
 pub const PLAYER_NAME_CHAR_LEN: usize = 16;
-const DEFAULT_BAR_LENGTH: u8 = 5;
-const DEFAULT_BALL_VELOCITY_X: i8 = 3;
-const DEFAULT_BALL_VELOCITY_Y: i8 = 1;
-const DEFAULT_PADDLE_WIDTH: u16 = 3;
-const STARTING_POWER_MOVES: u8 = 10;
 const DEFAULT_DIFFICULTY: f32 = 1.0;
+pub const DEFAULT_TARGET_SCORE: u32 = 5;
 
 /// Physics space dimensions matching the server's GAME_CONSTANTS.
 /// All network ball/paddle coordinates are in this space.
 pub const COURT_WIDTH: f32 = 40.0;
 pub const COURT_HEIGHT: f32 = 12.0;
 pub const COURT_PADDLE_HEIGHT: f32 = 3.0;
-
-#[derive(Debug, Clone, Copy)]
-struct ComputerAI {
-    reaction_delay: f32,     // Time before reacting to ball direction change
-    last_ball_direction: i8, // Track ball direction changes
-    reaction_timer: f32,     // Current reaction delay timer
-    prediction_error: f32,   // How far off the prediction can be
-    max_speed: f32,          // Maximum movement speed
-    current_speed: f32,      // Current movement speed (with acceleration)
-    target_position: f32,    // Where the AI wants to move
-    // difficulty: f32,         // 0.0 to 1.0, affects all parameters
-    fatigue: f32,         // Increases over time, affects performance
-    last_update: Instant, // For delta time calculations
+/// Ball is treated as a square this size whose top-left corner is (x, y).
+pub const COURT_BALL_SIZE: f32 = 1.0;
+/// Distance from each goal line to the paddle face.
+pub const COURT_PADDLE_FACE_X: f32 = 1.5;
+
+/// Rows a paddle takes in a court `height` rows tall
+fn paddle_rows(height: u16) -> u16 {
+    ((COURT_PADDLE_HEIGHT / COURT_HEIGHT * height as f32).round() as u16).clamp(2, height.max(2))
 }
 
-#[derive(Debug, Default)]
-pub struct Player {
-    pub name: [char; PLAYER_NAME_CHAR_LEN],
-    pub score: u32,
-
-    pub power_moves_left: u8,
-    pub last_power_used_at: Option<Instant>,
-
-    pub bar_position: u16,
-    pub bar_length: u8,
+/// Row offset of something `size` court units tall, drawn `rows` rows tall, whose
+/// top is at court `y`: top and bottom of the court line up with the first and last rows.
+fn court_row(y: f32, size: f32, rows: u16, height: u16) -> u16 {
+    let free = height.saturating_sub(rows) as f32;
+    (y / (COURT_HEIGHT - size) * free).round().clamp(0.0, free) as u16
+}
 
-    pub is_computer: bool,
-    computer_ai: Option<ComputerAI>,
+/// Column offset of the (two cells wide) ball at court `x`, in a court `width`
+/// columns wide: the paddle faces line up with the inside edges of the drawn bars.
+fn court_col(x: f32, width: u16) -> u16 {
+    let left_face = 3.0;
+    let right_face = width.saturating_sub(6) as f32;
+    let face_to_face = COURT_WIDTH - 2.0 * COURT_PADDLE_FACE_X - COURT_BALL_SIZE;
+    let col = left_face + (x - COURT_PADDLE_FACE_X) / face_to_face * (right_face - left_face);
+    col.round().clamp(0.0, width.saturating_sub(2) as f32) as u16
 }
 
+/// Network games: the server's ball and where it is drawn
 #[derive(Debug, Default)]
-struct Ball {
-    position: [u16; 2],
-    velocity: [i8; 2],
-    is_powered: bool,
+struct NetBall {
     /// Server-authoritative position in physics/court units (WithNetwork mode only)
     net_x: f32,
     net_y: f32,
     /// Server-reported velocity (court units / server tick, WithNetwork mode only)
     net_dx: f32,
     net_dy: f32,
-    /// Client-side smoothed display position (dead reckoning, WithNetwork mode only)
+    /// Client-side smoothed display position (see `smoothing`, WithNetwork mode only)
     render_x: f32,
     render_y: f32,
 }
@@ -80,19 +78,21 @@ struct Ball {
 pub enum GameType {
     ScreenSaver,
     WithNetwork,
+    /// Two humans on one keyboard, local physics
+    LocalVersus,
+    /// Human (P1) against ComputerAI (P2), local physics
+    VsComputer,
 }
 
-
 #[derive(Debug)]
 pub struct Game {
     game_type: GameType,
-    players: [Player; 2],
-    ball: Ball,
+    /// Paddles, scores and (local modes) the ball physics
+    sim: Sim,
+    net_ball: NetBall,
     game_area: Rect,
     last_update: Instant,
     is_paused: bool,
-    scored_keep_display: bool,
-    difficulty: f32,
     should_exit: bool,
     theme: GameTheme,
     /// For network games: which player index (0 or 1) this client controls.
@@ -103,6 +103,28 @@ pub struct Game {
     /// True once the first BallUpdate from the server has been received.
     /// Prevents rendering the ball at its dummy initial position before server data arrives.
     has_ball_data: bool,
+    /// Local modes: first player to reach this score wins. None = endless (ScreenSaver)
+    /// or decided by the server (WithNetwork).
+    target_score: Option<u32>,
+    keys: KeyBindings,
+    /// Network games: set while the MQTT session is down. Play is frozen and
+    /// this text is drawn over the court until the connection comes back.
+    connection_banner: Option<String>,
+    /// Network games: turns server ball packets into a per-frame render position
+    ball_smoother: BallSmoother,
+    /// Network games: same for the remote paddles' packets, by player index
+    paddle_smoothers: [PaddleSmoother; 2],
+    /// Network games: watching, not playing. Both paddles come from the network.
+    spectating: bool,
+    /// Network games: latest round-trip / clock estimate from the ping topic
+    clock_sync: Option<ClockSync>,
+    /// Network games: stats for the F3 overlay
+    diagnostics: Option<NetDiagnostics>,
+    show_diagnostics: bool,
+    /// Network games: the chat pane (T)
+    chat: Option<ChatPane>,
+    /// Replays: playback position and keys, drawn in place of the controls
+    replay_status: Option<String>,
 }
 
 impl Game {
@@ -110,57 +132,217 @@ impl Game {
         self.theme = theme;
     }
 
+    /// Theme currently shown (may have been changed from the pause menu)
+    pub fn get_theme(&self) -> GameTheme {
+        self.theme
+    }
+
+    pub fn set_key_bindings(&mut self, keys: KeyBindings) {
+        self.keys = keys;
+    }
+
+    /// Local modes: end the match when a player reaches `target` points
+    pub fn set_target_score(&mut self, target: u32) {
+        self.target_score = Some(target.max(1));
+    }
+
+    /// Local modes: let paddle movement bend returns
+    pub fn set_spin(&mut self, spin: bool) {
+        self.sim.spin = spin;
+    }
+
+    /// Local modes: how the ball speeds up over a rally
+    pub fn set_ramp(&mut self, ramp: SpeedRamp) {
+        self.sim.ramp = ramp.sanitized();
+    }
+
+    /// Network games: the rally count from the server's state message
+    pub fn set_rally(&mut self, rally: u32) {
+        self.sim.rally = rally;
+    }
+
+    /// True once a local match has been won (network games are decided by the server)
+    pub fn is_game_over(&self) -> bool {
+        match self.target_score {
+            Some(target) => self.sim.players.iter().any(|p| p.score >= target),
+            None => false,
+        }
+    }
+
+    pub fn is_networked(&self) -> bool {
+        self.game_type == GameType::WithNetwork
+    }
+
+    /// Local modes: reset scores, power moves and the ball for a rematch
+    pub fn restart_match(&mut self) {
+        self.sim.restart();
+    }
+
+    /// Freeze the network game behind a banner (e.g. "Reconnecting (attempt 2)"), or resume with None
+    pub fn set_connection_banner(&mut self, banner: Option<String>) {
+        self.connection_banner = banner;
+    }
+
     /// Set which player index (0-based) this client controls in a network game
     pub fn set_local_player_index(&mut self, index: usize) {
         self.local_player_index = Some(index);
     }
 
-    /// Drive the opponent's paddle from a network event.
+    /// Network games: watch without a paddle
+    pub fn set_spectator(&mut self) {
+        self.spectating = true;
+        self.local_player_index = None;
+    }
+
+    pub fn is_spectating(&self) -> bool {
+        self.spectating
+    }
+
+    /// Buffer a remote paddle packet. The bar follows it through the paddle
+    /// smoother each frame (see `smoothing`).
+    pub fn set_remote_paddle(&mut self, player_index: usize, msg: &PaddleMsg) {
+        let Some(smoother) = self.paddle_smoothers.get_mut(player_index) else {
+            return;
+        };
+        if !smoother.on_packet(msg, now_ms()) {
+            if let Some(d) = &mut self.diagnostics {
+                d.on_dropped();
+            }
+        }
+    }
+
+    /// Network games: the paddles driven by packets rather than this keyboard
+    fn remote_paddles(&self) -> &'static [usize] {
+        match (self.spectating, self.local_player_index) {
+            (true, _) => &[0, 1],
+            (false, Some(0)) => &[1],
+            (false, Some(_)) => &[0],
+            (false, None) => &[],
+        }
+    }
+
+    /// Move a paddle to a network position.
     /// y_physics is in court units (0 .. COURT_HEIGHT - COURT_PADDLE_HEIGHT = 9).
-    pub fn set_opponent_paddle(&mut self, player_index: usize, y_physics: f32) {
-        let inner_height = self.game_area.height.saturating_sub(2) as f32;
-        if inner_height <= 0.0 { return; }
-        let bar_len = self.players[player_index].bar_length as f32;
-        let max_y = (inner_height - bar_len).max(0.0);
-        // Physics y range: 0 .. (COURT_HEIGHT - COURT_PADDLE_HEIGHT)
-        // Terminal y range: 0 .. max_y
-        let phys_range = (COURT_HEIGHT - COURT_PADDLE_HEIGHT).max(1.0);
-        let terminal_y = (y_physics / phys_range * max_y).clamp(0.0, max_y);
-        self.players[player_index].bar_position = terminal_y as u16;
+    fn place_paddle(&mut self, player_index: usize, y_physics: f32) {
+        self.sim.players[player_index].y = y_physics.clamp(0.0, PADDLE_MAX_Y);
     }
 
     /// Store the authoritative ball state from the server (physics/court units).
-    /// Always snaps render_x/y to server truth — the server IS the physics engine.
-    /// No lerp: lerp caused the rendered ball to lag perpetually behind server position.
-    pub fn set_ball_from_network(&mut self, x: f32, y: f32, dx: f32, dy: f32) {
-        self.ball.net_x = x;
-        self.ball.net_y = y;
-        self.ball.net_dx = dx;
-        self.ball.net_dy = dy;
-        self.ball.render_x = x;
-        self.ball.render_y = y;
+    /// render_x/y follow it through the ball smoother each frame (see `smoothing`).
+    pub fn set_ball_from_network(&mut self, msg: &BallMsg) {
+        self.net_ball.net_x = msg.x;
+        self.net_ball.net_y = msg.y;
+        self.net_ball.net_dx = msg.dx;
+        self.net_ball.net_dy = msg.dy;
+        let now = now_ms();
+        let accepted = self.ball_smoother.on_packet(msg, now);
+        if let Some(d) = &mut self.diagnostics {
+            d.on_ball(now);
+            if !accepted {
+                d.on_dropped();
+            }
+        }
+        if !self.has_ball_data {
+            self.net_ball.render_x = msg.x;
+            self.net_ball.render_y = msg.y;
+        }
         self.has_ball_data = true;
     }
 
+    pub fn set_smoothing(&mut self, settings: &SmoothingSettings) {
+        self.ball_smoother = BallSmoother::new(settings);
+        self.paddle_smoothers = [PaddleSmoother::new(settings), PaddleSmoother::new(settings)];
+    }
+
+    pub fn set_clock_sync(&mut self, sync: ClockSync) {
+        self.clock_sync = Some(sync);
+    }
+
+    /// Enables the F3 network overlay, fed from the session's message counters
+    pub fn set_traffic_counters(&mut self, traffic: Arc<TrafficCounters>) {
+        self.diagnostics = Some(NetDiagnostics::new(traffic));
+    }
+
+    pub fn set_connection_status(&mut self, status: &str) {
+        if let Some(d) = &mut self.diagnostics {
+            d.set_status(status);
+        }
+    }
+
+    /// Network games: turn on the chat pane
+    pub fn enable_chat(&mut self) {
+        self.chat = Some(ChatPane::default());
+    }
+
+    pub fn receive_chat(&mut self, msg: &ChatMsg) {
+        if let Some(chat) = &mut self.chat {
+            chat.receive(msg);
+        }
+    }
+
+    /// Chat lines typed since the last call, for the App to publish
+    pub fn take_chat_outbox(&mut self) -> Vec<String> {
+        self.chat.as_mut().map(ChatPane::take_outbox).unwrap_or_default()
+    }
+
+    /// Offer a key to the chat pane (the App calls this on the game-over screen,
+    /// where it reads the keys itself). True if the chat used it.
+    pub fn handle_chat_key(&mut self, code: KeyCode) -> bool {
+        let k = self.keys;
+        let bound = [k.p1_up, k.p1_down, k.p1_power, k.p2_up, k.p2_down, k.p2_power];
+        self.chat.as_mut().is_some_and(|chat| chat.handle_key(code, &bound))
+    }
+
+    /// Draw the chat pane over the court; the App calls this after any overlay of its own.
+    pub fn draw_chat(&self, frame: &mut Frame) {
+        if let Some(chat) = &self.chat {
+            chat.draw(frame, self.game_area, &self.theme.colors());
+        }
+    }
+
+    /// Network games: names from the server's state message, for the paddles this
+    /// client doesn't drive. Shown in the title bar and the game-over overlay.
+    pub fn set_remote_names(&mut self, names: [Option<&str>; 2]) {
+        for &idx in self.remote_paddles() {
+            if let Some(name) = names[idx].filter(|n| !n.trim().is_empty()) {
+                self.sim.players[idx].name = string_to_char_array(name);
+            }
+        }
+    }
+
+    /// Replays: draw the recorded court instead of live packets. Set up the game
+    /// as a spectator so both paddles and names come from the recording.
+    pub fn show_replay_frame(&mut self, replay: &ReplayFrame, status: String) {
+        if let Some((x, y)) = replay.ball {
+            self.net_ball.render_x = x;
+            self.net_ball.render_y = y;
+            self.has_ball_data = true;
+        }
+        for (idx, y) in replay.paddles.iter().enumerate() {
+            if let Some(y) = y {
+                self.place_paddle(idx, *y);
+            }
+        }
+        self.set_scores(replay.scores.0, replay.scores.1);
+        self.set_rally(replay.rally);
+        self.set_remote_names([replay.names[0].as_deref(), replay.names[1].as_deref()]);
+        self.replay_status = Some(status);
+    }
+
     /// Overwrite scores from the server state message
     pub fn set_scores(&mut self, p1: u32, p2: u32) {
-        self.players[0].score = p1;
-        self.players[1].score = p2;
+        self.sim.players[0].score = p1;
+        self.sim.players[1].score = p2;
     }
 
     /// Get current scores (p1, p2)
     pub fn get_scores(&self) -> (u32, u32) {
-        (self.players[0].score, self.players[1].score)
+        (self.sim.players[0].score, self.sim.players[1].score)
     }
 
     /// Return the local paddle Y in physics/court units (for publishing to server).
     pub fn get_paddle_physics_y(&self, player_index: usize) -> f32 {
-        let inner_height = self.game_area.height.saturating_sub(2) as f32;
-        let bar_len = self.players[player_index].bar_length as f32;
-        let max_terminal = (inner_height - bar_len).max(1.0);
-        let terminal_y = self.players[player_index].bar_position as f32;
-        // Map terminal position → physics position (0 .. COURT_HEIGHT - COURT_PADDLE_HEIGHT)
-        (terminal_y / max_terminal) * (COURT_HEIGHT - COURT_PADDLE_HEIGHT)
+        self.sim.players[player_index].y
     }
 }
 
@@ -172,60 +354,13 @@ impl Game {
         difficulty: Option<f32>,
     ) -> Self {
         let theme = GameTheme::Monokai;
-
         let final_difficulty = difficulty.unwrap_or(DEFAULT_DIFFICULTY).clamp(0.0, 2.0);
-        let ai_player = ComputerAI {
-            reaction_delay: 0.2 + (2.0 - final_difficulty) * 0.5, // 0.2-0.7 seconds
-            last_ball_direction: 0,
-            reaction_timer: 0.0,
-            prediction_error: 2.0 + (1.0 - final_difficulty) * 2.5, // 2-7 units error
-            max_speed: 0.8 + final_difficulty * 0.85,               // 0.8-2.5 speed
-            current_speed: 0.0,
-            target_position: 0.0,
-            fatigue: 0.0,
-            last_update: Instant::now(),
-        };
-
-        let player1 = Player {
-            name: string_to_char_array(player_names[0]),
-            bar_position: (game_area.height / 2).saturating_sub((DEFAULT_BAR_LENGTH / 2) as u16),
-            bar_length: DEFAULT_BAR_LENGTH,
-            is_computer: false,
-            computer_ai: if game_type == GameType::ScreenSaver {
-                Some(ai_player.clone())
-            } else {
-                None
-            },
-            power_moves_left: STARTING_POWER_MOVES,
-            last_power_used_at: None,
-            score: 0,
-        };
-
-        let player2 = Player {
-            name: string_to_char_array(player_names[1]),
-            bar_position: (game_area.height / 2).saturating_sub((DEFAULT_BAR_LENGTH / 2) as u16),
-            bar_length: DEFAULT_BAR_LENGTH,
-            is_computer: false,
-            computer_ai: if game_type == GameType::ScreenSaver {
-                Some(ai_player.clone())
-            } else {
-                None
-            },
-            power_moves_left: STARTING_POWER_MOVES,
-            last_power_used_at: None,
-            score: 0,
-        };
+        let sim = Sim::new(player_names, &game_type, final_difficulty, rand::random());
 
         Self {
             game_type,
-            players: [player1, player2],
-            ball: Ball {
-                position: [
-                    game_area.width.saturating_sub(4) / 2,
-                    game_area.height.saturating_sub(4) / 2,
-                ],
-                velocity: [DEFAULT_BALL_VELOCITY_X, DEFAULT_BALL_VELOCITY_Y],
-                is_powered: false,
+            sim,
+            net_ball: NetBall {
                 net_x: COURT_WIDTH / 2.0,
                 net_y: COURT_HEIGHT / 2.0,
                 net_dx: 0.0,
@@ -234,15 +369,27 @@ impl Game {
                 render_y: COURT_HEIGHT / 2.0,
             },
             last_update: Instant::now(),
-            game_area: game_area,
+            game_area,
             is_paused: false,
-            scored_keep_display: false,
-            difficulty: final_difficulty,
             should_exit: false,
             theme,
             local_player_index: None,
             pending_serve: false,
             has_ball_data: false,
+            target_score: None,
+            keys: KeyBindings::default(),
+            connection_banner: None,
+            ball_smoother: BallSmoother::new(&SmoothingSettings::default()),
+            paddle_smoothers: [
+                PaddleSmoother::new(&SmoothingSettings::default()),
+                PaddleSmoother::new(&SmoothingSettings::default()),
+            ],
+            spectating: false,
+            clock_sync: None,
+            diagnostics: None,
+            show_diagnostics: false,
+            chat: None,
+            replay_status: None,
         }
     }
 
@@ -255,64 +402,55 @@ impl Game {
     }
 
     pub fn get_player(&self, index: usize) -> &Player {
-        &self.players[index]
+        &self.sim.players[index]
     }
 
     fn move_player(&mut self, player_index: usize, direction: i8) {
-        if direction == 0 {
-            return;
-        }
-
-        let player = &mut self.players[player_index];
-
-        if player.is_computer {
-            return;
-        }
-
-        let step: u16 = 1;
-
-        if direction > 0 {
-            // up
-            if player.bar_position > 0 {
-                player.bar_position -= step;
-            }
-        } else {
-            // down
-            let inner_height = self.game_area.height.saturating_sub(2);
-            if player.bar_position + (player.bar_length as u16) < inner_height {
-                player.bar_position += step;
-            }
-        }
+        self.sim.move_player(player_index, direction);
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) {
         let code = key_event.code;
+        if self.handle_chat_key(code) {
+            return;
+        }
         match code {
             KeyCode::Esc | KeyCode::Char('q') => self.should_exit = true,
+            KeyCode::F(3) => self.show_diagnostics = !self.show_diagnostics,
             KeyCode::Char('p') => {
                 if self.game_type != GameType::WithNetwork {
                     self.toggle_pause();
                 }
             }
+            _ if self.spectating => {}
             _ => {
+                let k = self.keys;
                 if let Some(local_idx) = self.local_player_index {
                     // Network mode: Up/W move local paddle up, Down/S move it down.
                     // Both key sets work so either hand feels natural.
                     match code {
-                        KeyCode::Up | KeyCode::Char('w') => self.move_player(local_idx, 1),
-                        KeyCode::Down | KeyCode::Char('s') => self.move_player(local_idx, -1),
+                        c if c == k.p1_up || c == k.p2_up => self.move_player(local_idx, 1),
+                        c if c == k.p1_down || c == k.p2_down => self.move_player(local_idx, -1),
                         KeyCode::Char(' ') | KeyCode::Enter => self.pending_serve = true,
                         _ => {}
                     }
+                } else if self.game_type == GameType::VsComputer {
+                    // Single human on the left: either key set moves, either power key fires
+                    match code {
+                        c if c == k.p1_up || c == k.p2_up => self.move_player(0, 1),
+                        c if c == k.p1_down || c == k.p2_down => self.move_player(0, -1),
+                        c if c == k.p1_power || c == k.p2_power => self.power_move(0),
+                        _ => {}
+                    }
                 } else {
                     // Local / screensaver mode: original two-player bindings
                     match code {
-                        KeyCode::Char('/') => self.power_move(0),
-                        KeyCode::Up => self.move_player(0, 1),
-                        KeyCode::Down => self.move_player(0, -1),
-                        KeyCode::Char(' ') => self.power_move(1),
-                        KeyCode::Char('w') => self.move_player(1, 1),
-                        KeyCode::Char('s') => self.move_player(1, -1),
+                        c if c == k.p1_power => self.power_move(0),
+                        c if c == k.p1_up => self.move_player(0, 1),
+                        c if c == k.p1_down => self.move_player(0, -1),
+                        c if c == k.p2_power => self.power_move(1),
+                        c if c == k.p2_up => self.move_player(1, 1),
+                        c if c == k.p2_down => self.move_player(1, -1),
                         _ => {}
                     }
                 }
@@ -324,6 +462,9 @@ impl Game {
         // Always scroll the local player's paddle.
         // Previously hardcoded to 0, which meant player-2 clients were accidentally
         // moving the opponent's (index 0) rendered paddle on their screen.
+        if self.spectating {
+            return;
+        }
         let idx = self.local_player_index.unwrap_or(0);
         match mouse_event.kind {
             MouseEventKind::ScrollUp => self.move_player(idx, 1),
@@ -346,6 +487,23 @@ impl Game {
         Ok(())
     }
 
+    // key events while the connection banner is up: only quitting (and F3) works
+    fn handle_disconnected_events(&mut self) -> io::Result<()> {
+        while event::poll(Duration::from_millis(5))? {
+            if let Event::Key(key_event) = event::read()? {
+                if key_event.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key_event.code {
+                    KeyCode::Esc | KeyCode::Char('q') => self.should_exit = true,
+                    KeyCode::F(3) => self.show_diagnostics = !self.show_diagnostics,
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
     // key events while paused (pause/options popup)
     fn handle_pause_events(&mut self) -> io::Result<()> {
         while event::poll(Duration::from_millis(5))? {
@@ -368,10 +526,10 @@ impl Game {
                             };
                         }
                         KeyCode::Left => {
-                            self.difficulty = (self.difficulty - 0.1).clamp(0.0, 2.0);
+                            self.sim.difficulty = (self.sim.difficulty - 0.1).clamp(0.0, 2.0);
                         }
                         KeyCode::Right => {
-                            self.difficulty = (self.difficulty + 0.1).clamp(0.0, 2.0);
+                            self.sim.difficulty = (self.sim.difficulty + 0.1).clamp(0.0, 2.0);
                         }
                         _ => {}
                     }
@@ -386,259 +544,8 @@ impl Game {
         self.is_paused = !self.is_paused;
     }
 
-    /**
-     * 1 -> ball collision with Player 1's bar
-     * 2 -> ball collision with Player 2's bar
-     * None -> no collision, ball position updated normally
-     */
-    fn update_ball_position(&mut self) -> Option<u8> {
-        let inner_width = self.game_area.width.saturating_sub(3);
-        let inner_height = self.game_area.height.saturating_sub(2);
-
-        let players = &self.players;
-        let ball = &mut self.ball;
-
-        let new_x = ball.position[0].saturating_add_signed(ball.velocity[0] as i16);
-        let new_y = ball.position[1].saturating_add_signed(ball.velocity[1] as i16);
-
-        // collision with top and bottom walls
-        if new_y == 0 || new_y >= inner_height {
-            ball.velocity[1] = -ball.velocity[1];
-            ball.position[1] = if new_y == 0 { 0 } else { inner_height - 1 };
-        } else {
-            ball.position[1] = new_y;
-        }
-
-        if !self.scored_keep_display {
-            // ball collision with Player 1's bar (left side)
-            if new_x <= DEFAULT_PADDLE_WIDTH && ball.velocity[0] < 0 {
-                if new_y >= players[0].bar_position
-                    && new_y < players[0].bar_position + players[0].bar_length as u16
-                {
-                    ball.velocity[0] = -ball.velocity[0];
-                    ball.position[0] = DEFAULT_PADDLE_WIDTH;
-                    return Some(1);
-                }
-            }
-
-            // ball collision with Player 2's bar (right side)
-            if new_x >= inner_width - DEFAULT_PADDLE_WIDTH - 1 && ball.velocity[0] > 0 {
-                if new_y >= players[1].bar_position
-                    && new_y < players[1].bar_position + players[1].bar_length as u16
-                {
-                    ball.velocity[0] = -DEFAULT_BALL_VELOCITY_X;
-                    ball.position[0] = inner_width - DEFAULT_PADDLE_WIDTH - 1;
-                    ball.is_powered = false;
-                    return Some(2);
-                }
-            }
-        }
-
-        // ball went off screen (reset)
-        if new_x < DEFAULT_PADDLE_WIDTH || new_x > inner_width - DEFAULT_PADDLE_WIDTH - 1 {
-            if new_x <= 0 || new_x >= inner_width {
-                // Ball exited the screen: left or right
-                if new_x <= 0 {
-                    // ball exited on the left → player missed → computer scores
-                    self.players[1].score += 1;
-                } else {
-                    // ball exited on the right → computer missed → player scores
-                    self.players[0].score += 1;
-                }
-
-                // reset ball to center
-                ball.position = [
-                    inner_width / 2,
-                    rand::random_range(1..inner_height.saturating_sub(1)),
-                ];
-
-                let random_number: i16 = rand::random_range(0..=1);
-                let direction = if random_number == 0 { 1 } else { -1 };
-
-                ball.velocity[0] = direction * DEFAULT_BALL_VELOCITY_X;
-                ball.is_powered = false;
-
-                self.scored_keep_display = false;
-
-                return None;
-            } else {
-                // keep drawing
-                self.scored_keep_display = true;
-                ball.position[0] = new_x;
-            }
-        } else {
-            ball.position[0] = new_x;
-        }
-
-        None
-    }
-
-    fn update_computer_player(&mut self, player_index: usize) {
-        let computer = &mut self.players[player_index];
-        let ball = &self.ball;
-
-        if computer.computer_ai.is_none() {
-            return;
-        }
-        let ai = computer.computer_ai.as_mut().unwrap();
-
-        let inner_height = self.game_area.height;
-        let paddle_x = if player_index == 0 {
-            DEFAULT_PADDLE_WIDTH // Player 1's paddle is on the left
-        } else {
-            self.game_area.width - DEFAULT_PADDLE_WIDTH // Player 2's paddle is on the right
-        };
-
-        // accumulate delta time to add fatigue
-        let dt = ai.last_update.elapsed().as_secs_f32();
-        ai.last_update = Instant::now();
-        // increase fatigue over time
-        if self.game_type == GameType::ScreenSaver {
-            ai.fatigue = (ai.fatigue + dt * 0.001).min(0.05); // much less fatigue for AI vs AI
-        } else {
-            ai.fatigue = (ai.fatigue + dt * 0.009).min(0.3);
-        }
-
-        // now, let's calculate the direction the ball is moving towards
-        let ball_direction_x: i8 = ball.velocity[0].signum(); // one of these 3 -> { -1, 0, 1 }
-
-        // if ball direction changed, add a reaction timer
-        if ball_direction_x != ai.last_ball_direction && ball_direction_x != 0 {
-            ai.last_ball_direction = ball_direction_x;
-            ai.reaction_timer = ai.reaction_delay + ai.fatigue * 0.5;
-        }
-
-        ai.reaction_timer = (ai.reaction_timer - dt).max(0.0);
-
-        // check if ball is coming towards computer paddle
-        let is_ball_coming = if player_index == 0 {
-            ball.velocity[0] < 0 // Player 1's paddle is on the left
-        } else {
-            ball.velocity[0] > 0 // Player 2's paddle is on the right
-        };
-
-        let paddle_center = computer.bar_position as f32 + computer.bar_length as f32 / 2.0;
-
-        if !is_ball_coming || ai.reaction_timer > 0.0 {
-            // neutral positioning
-            // slowly drift towards the center
-
-            let center_y = inner_height / 2;
-            ai.target_position = paddle_center + (center_y as f32 - paddle_center) * 0.1;
-        } else {
-            // active/predictive positioning
-            // "predict" ball position with wall bounces
-
-            let time_to_paddle_x =
-                (paddle_x as f32 - ball.position[0] as f32) / ball.velocity[0] as f32;
-            let mut pred_y = ball.position[1] as f32 + ball.velocity[1] as f32 * time_to_paddle_x;
-
-            // simulate top and bottom wall bounces
-            while pred_y < 0.0 || pred_y > inner_height as f32 {
-                if pred_y < 0.0 {
-                    pred_y = -pred_y; // bounce off top wall
-                } else {
-                    pred_y = 2.0 * inner_height as f32 - pred_y; // bounce off bottom wall
-                }
-            }
-
-            // sprinkle some prediction errors -,-
-            let (error_magnitude, oops_chance, random_chance) = match self.game_type {
-                GameType::ScreenSaver => (ai.prediction_error * 0.3, 0.01, 0.02), // much less error
-                _ => (
-                    ai.prediction_error * (1.0 + ai.fatigue),
-                    0.05 + ai.fatigue * 0.1,
-                    0.1,
-                ),
-            };
-            let prediction_error = (random::<f32>() - 0.5) * error_magnitude;
-            pred_y += prediction_error;
-
-            // make big oopsies occasionally
-            if random::<f32>() < oops_chance {
-                pred_y += (random::<f32>() - 0.5) * 3.0;
-            }
-
-            // add some final randomness
-            if random::<f32>() < random_chance {
-                pred_y += (random::<f32>() - 0.5) * 1.0;
-            }
-
-            // clamp to fix
-            pred_y = pred_y.clamp(0.0, (inner_height - computer.bar_length as u16) as f32);
-
-            ai.target_position = pred_y;
-        }
-
-        // smooth movement with acceleration
-        let distance_to_target = ai.target_position - paddle_center;
-        let desired_speed = distance_to_target.abs().min(ai.max_speed);
-        let acceleration = 2.0;
-        if distance_to_target.abs() > 0.5 {
-            ai.current_speed = (ai.current_speed + acceleration * dt).min(desired_speed);
-        } else {
-            ai.current_speed = (ai.current_speed - acceleration * dt * 2.0).max(0.0);
-        }
-
-        // add some jitter and behavioral quirks
-        let jitter = match self.game_type {
-            GameType::ScreenSaver => (random::<f32>() - 0.5) * 0.02 * (1.0 + ai.fatigue),
-            _ => 0.0,
-        };
-        let movement = distance_to_target.signum() * ai.current_speed + jitter;
-
-        let final_movement = match self.game_type {
-            GameType::ScreenSaver => movement,
-            _ => movement,
-        };
-
-        // apply new position with clamping
-        let new_pos = (paddle_center + final_movement).clamp(
-            computer.bar_length as f32 / 2.0,
-            (inner_height - computer.bar_length as u16) as f32,
-        );
-
-        computer.bar_position = (new_pos - computer.bar_length as f32 / 2.0) as u16;
-    }
-
     fn power_move(&mut self, player_index: usize) {
-        let player = &mut self.players[player_index];
-
-        if player.power_moves_left <= 0 {
-            return; // no power move left
-        }
-
-        let ball = &mut self.ball;
-
-        let is_ball_approaching = if player_index == 0 {
-            ball.velocity[0] < 0
-        } else {
-            ball.velocity[0] > 0
-        };
-
-        let within_bar = ball.position[1] >= player.bar_position
-            && ball.position[1] < player.bar_position + player.bar_length as u16;
-
-        let min_range = 4.0;
-        let max_range = 12.0;
-        let allowed_range = (max_range - min_range) * (1.0 - self.difficulty) + min_range;
-        let allowed_range = allowed_range.round() as u16;
-
-        let within_x = if player_index == 0 {
-            ball.position[0] > 1 && ball.position[0] < 1 + allowed_range
-        } else {
-            let right_edge = self.game_area.width.saturating_sub(1);
-            ball.position[0] > right_edge.saturating_sub(allowed_range)
-                && ball.position[0] < right_edge.saturating_sub(1)
-        };
-
-        if is_ball_approaching && within_bar && within_x {
-            // power move: send ball flying in the correct direction
-            ball.velocity[0] = if player_index == 0 { 6 } else { -6 };
-            ball.is_powered = true;
-            player.power_moves_left -= 1;
-            player.last_power_used_at = Some(Instant::now());
-        }
+        self.sim.power_move(player_index);
     }
 
     fn draw_core_elements(&self, frame: &mut Frame) {
@@ -658,20 +565,19 @@ impl Game {
         // back into the terminal diff and appear as ghost objects.
         frame.render_widget(Clear, inner_area);
 
+        // Everything moves in court units; map it onto whatever the terminal gives us
+        let bar_rows = paddle_rows(inner_area.height);
+
         // Player 1 bar (left side)
         let player1 = self.get_player(0);
         let bar_1_area = Rect::new(
             inner_area.x,
-            inner_area.y + player1.bar_position,
+            inner_area.y + court_row(player1.y, COURT_PADDLE_HEIGHT, bar_rows, inner_area.height),
             3,
-            player1.bar_length as u16,
+            bar_rows,
         );
-        let bar_1_color = if let Some(last) = player1.last_power_used_at {
-            if last.elapsed() < Duration::from_millis(200) {
-                colors.player_bar_power
-            } else {
-                colors.player_bar
-            }
+        let bar_1_color = if self.sim.power_flash(0) {
+            colors.player_bar_power
         } else {
             colors.player_bar
         };
@@ -683,17 +589,13 @@ impl Game {
         // Player 2 bar (right side)
         let player2 = self.get_player(1);
         let bar_2_area = Rect::new(
-            inner_area.x + inner_area.width - 4,
-            inner_area.y + player2.bar_position,
+            inner_area.x + inner_area.width.saturating_sub(4),
+            inner_area.y + court_row(player2.y, COURT_PADDLE_HEIGHT, bar_rows, inner_area.height),
             3,
-            player2.bar_length as u16,
+            bar_rows,
         );
-        let bar_2_color = if let Some(last) = player2.last_power_used_at {
-            if last.elapsed() < Duration::from_millis(200) {
-                colors.player_bar_power
-            } else {
-                colors.player_bar
-            }
+        let bar_2_color = if self.sim.power_flash(1) {
+            colors.player_bar_power
         } else {
             colors.player_bar
         };
@@ -705,17 +607,13 @@ impl Game {
         // Ball — always render. The Clear widget above wipes stale buffer content
         // each frame, so the phantom-before-server-data problem no longer exists.
         {
-            let (ball_col, ball_row) = if self.game_type == GameType::WithNetwork {
-                let iw = inner_area.width.saturating_sub(2) as f32;
-                // Server wall-bounce clamps ball Y to [0, COURT_HEIGHT-1].
-                // Map that range to the full inner height (minus 1 for ball height=1).
-                let ih = inner_area.height.saturating_sub(1) as f32;
-                let col = ((self.ball.render_x / COURT_WIDTH) * iw).clamp(0.0, iw) as u16;
-                let row = ((self.ball.render_y / (COURT_HEIGHT - 1.0)) * ih).clamp(0.0, ih) as u16;
-                (col, row)
+            let (x, y) = if self.game_type == GameType::WithNetwork {
+                (self.net_ball.render_x, self.net_ball.render_y)
             } else {
-                (self.ball.position[0], self.ball.position[1])
+                (self.sim.ball.x, self.sim.ball.y)
             };
+            let ball_col = court_col(x, inner_area.width);
+            let ball_row = court_row(y, COURT_BALL_SIZE, 1, inner_area.height);
             let ball_area = Rect::new(
                 inner_area.x + ball_col,
                 inner_area.y + ball_row,
@@ -742,7 +640,17 @@ impl Game {
         let game_area = layout[0];
         self.set_area(game_area);
 
-        let title = self.get_block_title("terminal.pong");
+        let app_name = if self.replay_status.is_some() {
+            "terminal.pong [replay]"
+        } else if self.spectating {
+            "terminal.pong [spectator]"
+        } else {
+            "terminal.pong"
+        };
+        let title = match self.sim.rally {
+            0 => self.get_block_title(app_name),
+            rally => self.get_block_title(&format!("{} · rally {}", app_name, rally)),
+        };
         let block = Block::default()
             .title(title)
             .borders(Borders::ALL)
@@ -753,10 +661,49 @@ impl Game {
 
         self.draw_core_elements(frame);
 
+        let k = self.keys;
+        let ping = match self.clock_sync {
+            Some(sync) => format!("Ping {:.0}ms ±{:.0}", sync.rtt_ms, sync.jitter_ms),
+            None => "Ping --".to_string(),
+        };
         let controls_text = match self.local_player_index {
-            Some(0) => " P1: ↑/W = up  ↓/S = down  Space = serve  |  Esc = Quit ",
-            Some(1) => " P2: ↑/W = up  ↓/S = down  Space = serve  |  Esc = Quit ",
-            _ => " Player 1: ↑/↓  |  Player 2: W/S  |  P=Pause  |  Esc=Quit ",
+            _ if self.replay_status.is_some() => {
+                format!(" {} ", self.replay_status.as_deref().unwrap_or_default())
+            }
+            _ if self.spectating => {
+                format!(" ◉ SPECTATING  |  {}  F3 = Net  |  T = Chat  |  Esc = Quit ", ping)
+            }
+            Some(idx) => format!(
+                " P{}: {}/{} = up  {}/{} = down  Space = serve  |  {}  F3 = Net  |  T = Chat  |  Esc = Quit ",
+                idx + 1,
+                key_label(k.p1_up),
+                key_label(k.p2_up),
+                key_label(k.p1_down),
+                key_label(k.p2_down),
+                ping,
+            ),
+            _ if self.game_type == GameType::VsComputer => format!(
+                " You: {}/{} = up  {}/{} = down  {}/{} = power  |  P=Pause  |  Esc=Quit ",
+                key_label(k.p1_up),
+                key_label(k.p2_up),
+                key_label(k.p1_down),
+                key_label(k.p2_down),
+                key_label(k.p1_power),
+                key_label(k.p2_power),
+            ),
+            _ if self.game_type == GameType::LocalVersus => format!(
+                " P1: {}/{}  {} = power  |  P2: {}/{}  {} = power  |  P=Pause  |  Esc=Quit ",
+                key_label(k.p1_up),
+                key_label(k.p1_down),
+                key_label(k.p1_power),
+                key_label(k.p2_up),
+                key_label(k.p2_down),
+                key_label(k.p2_power),
+            ),
+            _ if self.game_type == GameType::ScreenSaver => {
+                " Demo: CPU vs CPU  |  P=Pause  |  Esc=Quit ".to_string()
+            }
+            _ => " Player 1: ↑/↓  |  Player 2: W/S  |  P=Pause  |  Esc=Quit ".to_string(),
         };
         let controls = Paragraph::new(controls_text)
             .block(
@@ -769,6 +716,27 @@ impl Game {
             .alignment(Alignment::Center);
         frame.render_widget(controls, layout[1]);
 
+        if self.show_diagnostics {
+            if let Some(d) = &self.diagnostics {
+                d.draw(frame, game_area, &colors, self.clock_sync, now_ms());
+            }
+        }
+
+        if let Some(banner) = &self.connection_banner {
+            let popup_area = centered_rect(40, 5, area.width, area.height);
+            let popup = Paragraph::new(format!("{}\n\nEsc = Quit", banner))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Double)
+                        .style(Style::default().fg(colors.accent).bg(colors.background)),
+                )
+                .style(Style::default().fg(colors.text))
+                .alignment(Alignment::Center);
+            frame.render_widget(Clear, popup_area);
+            frame.render_widget(popup, popup_area);
+        }
+
         if self.is_paused {
             // draw pause/options popup if paused
             let popup_width = 52;
@@ -783,7 +751,7 @@ impl Game {
             frame.render_widget(popup_block, popup_area);
 
             // options and instructions
-            let diff_label = match self.difficulty {
+            let diff_label = match self.sim.difficulty {
                 d if d < 0.6 => "Easy",
                 d if d < 1.3 => "Normal",
                 _ => "Hard",
@@ -799,7 +767,7 @@ impl Game {
             };
             let options_text = format!(
                 "\n  Difficulty: {} ({:.2})\n [←/→] Adjust  [D] Toggle Theme (Current: {})\n  [P/Enter] Resume  [Esc] Quit\n",
-                diff_label, self.difficulty, theme_label
+                diff_label, self.sim.difficulty, theme_label
             );
             let options = Paragraph::new(options_text)
                 .style(Style::default().fg(colors.text))
@@ -824,7 +792,17 @@ impl Game {
             return Ok(true);
         }
 
-        if self.game_type == GameType::WithNetwork {
+        if let Some(d) = &mut self.diagnostics {
+            d.update();
+        }
+
+        if self.game_type == GameType::WithNetwork && self.connection_banner.is_some() {
+            // Connection down: hold the paddle where it is until the session is back
+            self.handle_disconnected_events()?;
+            if self.should_exit {
+                return Ok(false);
+            }
+        } else if self.game_type == GameType::WithNetwork {
             // Network mode: server owns all ball physics.
             // Process input every frame — no timer gate. The 40fps timer was built
             // for local ball physics speed control and has no purpose here; it only
@@ -833,9 +811,19 @@ impl Game {
             if self.should_exit {
                 return Ok(false);
             }
+            let now = now_ms();
+            if let Some((x, y)) = self.ball_smoother.position(now) {
+                self.net_ball.render_x = x;
+                self.net_ball.render_y = y;
+            }
+            for &idx in self.remote_paddles() {
+                if let Some(y) = self.paddle_smoothers[idx].position(now) {
+                    self.place_paddle(idx, y);
+                }
+            }
         } else {
-            // ScreenSaver: local physics at difficulty-scaled FPS (15–40 Hz).
-            let fps = 15.0_f32 + 25.0 * self.difficulty;
+            // ScreenSaver / LocalVersus / VsComputer: local physics at difficulty-scaled FPS (15–40 Hz).
+            let fps = 15.0_f32 + 25.0 * self.sim.difficulty;
             let each_frame = (1000.0 / fps).round() as u64;
 
             if self.last_update.elapsed() >= Duration::from_millis(each_frame) {
@@ -843,14 +831,7 @@ impl Game {
                 if self.should_exit {
                     return Ok(false);
                 }
-                if rand::random() {
-                    self.update_computer_player(0);
-                    self.update_computer_player(1);
-                } else {
-                    self.update_computer_player(1);
-                    self.update_computer_player(0);
-                }
-                let _ = self.update_ball_position();
+                self.sim.tick(self.last_update.elapsed());
                 self.last_update = Instant::now();
             }
         }
@@ -858,11 +839,11 @@ impl Game {
         Ok(true)
     }
 
-    fn get_block_title(&self, app_name: &'static str) -> String {
+    fn get_block_title(&self, app_name: &str) -> String {
         let player1 = self.get_player(0);
         let p1_text = format!(
             "{} ({})",
-            player1.name.iter().collect::<String>().trim_end().to_string(),
+            player1.name.iter().collect::<String>().trim_end(),
             player1.score
         );
 
@@ -870,10 +851,10 @@ impl Game {
         let p2_text = format!(
             "({}) {}",
             player2.score,
-            player2.name.iter().collect::<String>().trim_start().to_string()
+            player2.name.iter().collect::<String>().trim_start()
         );
 
-        let used = p1_text.len() + app_name.len() + p2_text.len() + 6; // spaces + separators
+        let used = p1_text.chars().count() + app_name.chars().count() + p2_text.chars().count() + 6; // spaces + separators
         let total_width = self.game_area.width as usize;
         let dashes = total_width.saturating_sub(used) / 2;
 
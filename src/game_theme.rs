@@ -1,6 +1,8 @@
 use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum GameTheme {
     Monokai,
     Solarized,
@@ -22,6 +24,30 @@ pub struct ThemeColors {
 }
 
 impl GameTheme {
+    pub const ALL: [GameTheme; 7] = [
+        GameTheme::Monokai,
+        GameTheme::Solarized,
+        GameTheme::Dracula,
+        GameTheme::GruvboxDark,
+        GameTheme::Nord,
+        GameTheme::OneDark,
+        GameTheme::HighContrast,
+    ];
+
+    /// Parse a theme name as written in the config file ("gruvbox_dark"), ignoring case,
+    /// spaces, dashes and underscores so "Gruvbox Dark" and "gruvbox-dark" also work.
+    pub fn from_name(name: &str) -> Option<GameTheme> {
+        let wanted: String = name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        GameTheme::ALL.into_iter().find(|theme| {
+            let candidate: String = format!("{:?}", theme).to_ascii_lowercase();
+            candidate == wanted
+        })
+    }
+
     pub fn colors(&self) -> ThemeColors {
         match self {
             GameTheme::Monokai => ThemeColors {
@@ -35,3 +35,20 @@ pub fn string_to_char_array(s: &str) -> [char; PLAYER_NAME_CHAR_LEN] {
     array.copy_from_slice(&chars[0..PLAYER_NAME_CHAR_LEN]); // Copy the first 16 characters
     array
 }
+
+/// Playback position: "1:05", "12:30"
+pub fn format_clock(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Short human duration for list columns: "42s", "5m", "3h", "2d"
+pub fn format_age(ms: u64) -> String {
+    let secs = ms / 1000;
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86_399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
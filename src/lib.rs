@@ -0,0 +1,18 @@
+//! Shared modules for the `rust-pong` terminal client and the `pong-server` binary.
+
+pub mod broker;
+pub mod chat;
+pub mod cli;
+pub mod config;
+pub mod diagnostics;
+pub mod game;
+pub mod game_theme;
+pub mod helpers;
+pub mod lobby;
+pub mod matchmaker;
+pub mod network;
+pub mod replay;
+pub mod server;
+pub mod sim;
+pub mod smoothing;
+pub mod tls;
@@ -0,0 +1,317 @@
+use rumqttc::{Client, QoS};
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::game::PLAYER_NAME_CHAR_LEN;
+use crate::network::{
+    self, GameStatus, LobbyMsg, NetworkConfig, Topics, LOBBY_FILTER, LOBBY_REFRESH_MS,
+};
+
+// ---------------------------------------------------------------------------
+// Lobby browser
+//
+// Every player in a game keeps a retained `LobbyMsg` on
+// `pong/lobby/{game_id}/p{n}` (see `network::connect`). Subscribing to
+// `pong/lobby/+/+` therefore replays one message per occupied slot, and
+// empty payloads arrive as slots are freed. Players re-announce every
+// `LOBBY_REFRESH_MS`; a slot not heard from for `LOBBY_TTL_MS` (by our own
+// clock, since announcers' clocks can be off by more than that) is dropped.
+//
+// Limitation: a player who vanishes can't clear their own announcement. The
+// MQTT last will is a `LeaveMsg` to the game server, and only the Rust
+// `pong-server` answers it by clearing the slot. Behind any other server a
+// vanished player's game stays listed until the TTL runs out, up to 90 s,
+// and a retained announcement replayed when browsing starts gets a full TTL.
+// ---------------------------------------------------------------------------
+
+/// Generous enough to ride out a missed refresh or two
+pub const LOBBY_TTL_MS: u64 = 3 * LOBBY_REFRESH_MS;
+
+#[derive(Debug, Clone)]
+pub enum LobbyEvent {
+    Connected,
+    /// Connection lost; the session keeps retrying
+    Disconnected,
+    Announced(LobbyMsg),
+    Left { game_id: String, player: u8 },
+}
+
+/// A browsing session. Dropping it disconnects.
+pub struct LobbySession {
+    pub rx: mpsc::Receiver<LobbyEvent>,
+    client: Client,
+}
+
+impl Drop for LobbySession {
+    fn drop(&mut self) {
+        self.client.try_disconnect().ok();
+    }
+}
+
+/// Watch the lobby on the broker in `config` (its game and role are ignored).
+/// Fails only if the TLS files can't be loaded.
+pub fn browse(config: &NetworkConfig) -> io::Result<LobbySession> {
+    let client_id = format!("rust-pong-browse-{:08x}", rand::random::<u32>());
+    let mut options = network::mqtt_options(
+        client_id,
+        &config.broker_host,
+        config.broker_port,
+        config.transport,
+        &config.ws_path,
+        &config.tls,
+    )?;
+    options.set_keep_alive(Duration::from_secs(5));
+    if let (Some(user), Some(pass)) = (config.username.clone(), config.password.clone()) {
+        options.set_credentials(user, pass);
+    }
+
+    let (client, mut connection) = Client::new(options, 16);
+    let (tx, rx) = mpsc::channel();
+    let sub_client = client.clone();
+    thread::spawn(move || {
+        for notification in connection.iter() {
+            let event = match notification {
+                Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                    sub_client.try_subscribe(LOBBY_FILTER, QoS::AtMostOnce).ok();
+                    Some(LobbyEvent::Connected)
+                }
+                Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(msg))) => {
+                    parse_announcement(&msg.topic, &msg.payload)
+                }
+                Ok(rumqttc::Event::Outgoing(rumqttc::Outgoing::Disconnect)) => break,
+                Err(_) => {
+                    thread::sleep(Duration::from_secs(1));
+                    Some(LobbyEvent::Disconnected)
+                }
+                _ => None,
+            };
+            if let Some(event) = event {
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(LobbySession { rx, client })
+}
+
+fn parse_announcement(topic: &str, payload: &[u8]) -> Option<LobbyEvent> {
+    let (game_id, player) = Topics::lobby_slot_of(topic)?;
+    if payload.is_empty() {
+        return Some(LobbyEvent::Left {
+            game_id: game_id.to_string(),
+            player,
+        });
+    }
+    serde_json::from_slice::<LobbyMsg>(payload)
+        .ok()
+        .filter(|msg| msg.game_id == game_id && msg.player == player)
+        .map(LobbyEvent::Announced)
+}
+
+/// A game as listed by the browser
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedGame {
+    pub game_id: String,
+    /// Name of whoever has been in the game longest
+    pub host: String,
+    /// Which of slots 1 and 2 are taken
+    pub taken: [bool; 2],
+    pub status: GameStatus,
+    /// When the host joined (their clock), ms
+    pub since: u64,
+}
+
+impl ListedGame {
+    pub fn players(&self) -> usize {
+        self.taken.iter().filter(|&&t| t).count()
+    }
+
+    /// First free player slot, if any
+    pub fn free_slot(&self) -> Option<u8> {
+        (1..=2).find(|&p| !self.taken[p as usize - 1])
+    }
+}
+
+/// An announcement and when we received it (our clock), ms
+#[derive(Debug)]
+struct Listing {
+    msg: LobbyMsg,
+    received: u64,
+}
+
+/// Folds `LobbyEvent`s into the list of games
+#[derive(Debug, Default)]
+pub struct LobbyBrowser {
+    slots: BTreeMap<String, [Option<Listing>; 2]>,
+}
+
+impl LobbyBrowser {
+    /// Apply `event`, received at `now` (local clock, ms)
+    pub fn apply(&mut self, event: LobbyEvent, now: u64) {
+        match event {
+            LobbyEvent::Announced(msg) => {
+                let slot = msg.player as usize - 1;
+                let game_id = msg.game_id.clone();
+                self.slots.entry(game_id).or_default()[slot] = Some(Listing { msg, received: now });
+            }
+            LobbyEvent::Left { game_id, player } => {
+                if let Some(slots) = self.slots.get_mut(&game_id) {
+                    slots[player as usize - 1] = None;
+                    if slots.iter().all(Option::is_none) {
+                        self.slots.remove(&game_id);
+                    }
+                }
+            }
+            LobbyEvent::Connected | LobbyEvent::Disconnected => {}
+        }
+    }
+
+    /// Forget announcements not refreshed within `LOBBY_TTL_MS` of `now`
+    pub fn expire(&mut self, now: u64) {
+        for slots in self.slots.values_mut() {
+            for slot in slots.iter_mut() {
+                if slot
+                    .as_ref()
+                    .is_some_and(|l| now.saturating_sub(l.received) > LOBBY_TTL_MS)
+                {
+                    *slot = None;
+                }
+            }
+        }
+        self.slots.retain(|_, slots| slots.iter().any(Option::is_some));
+    }
+
+    /// Games with a free slot first, then newest first
+    pub fn games(&self) -> Vec<ListedGame> {
+        let mut games: Vec<ListedGame> = self
+            .slots
+            .iter()
+            .filter_map(|(game_id, slots)| {
+                let msgs = || slots.iter().flatten().map(|l| &l.msg);
+                let host = msgs().min_by_key(|m| m.since)?;
+                let status = if msgs().any(|m| m.status == GameStatus::Playing) {
+                    GameStatus::Playing
+                } else {
+                    host.status.clone()
+                };
+                Some(ListedGame {
+                    game_id: game_id.clone(),
+                    host: host.name.clone(),
+                    taken: [slots[0].is_some(), slots[1].is_some()],
+                    status,
+                    since: host.since,
+                })
+            })
+            .collect();
+        games.sort_by_key(|g| (g.free_slot().is_none(), std::cmp::Reverse(g.since)));
+        games
+    }
+}
+
+/// Name to announce when none is configured: the OS user name
+pub fn default_player_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .map(|name| name.trim().chars().take(PLAYER_NAME_CHAR_LEN).collect::<String>())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "Player".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcement(game_id: &str, player: u8, timestamp: u64) -> LobbyMsg {
+        LobbyMsg {
+            game_id: game_id.to_string(),
+            player,
+            name: format!("p{player}"),
+            status: GameStatus::Waiting,
+            since: 1_000,
+            timestamp,
+        }
+    }
+
+    fn payload(msg: &LobbyMsg) -> Vec<u8> {
+        serde_json::to_vec(msg).unwrap()
+    }
+
+    #[test]
+    fn parses_an_announcement() {
+        let msg = announcement("demo", 2, 5_000);
+        match parse_announcement("pong/lobby/demo/p2", &payload(&msg)) {
+            Some(LobbyEvent::Announced(parsed)) => {
+                assert_eq!((parsed.game_id.as_str(), parsed.player), ("demo", 2));
+                assert_eq!(parsed.timestamp, 5_000);
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn empty_payload_frees_the_slot() {
+        match parse_announcement("pong/lobby/demo/p1", &[]) {
+            Some(LobbyEvent::Left { game_id, player }) => {
+                assert_eq!((game_id.as_str(), player), ("demo", 1));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_announcements_that_dont_match_their_topic() {
+        let msg = announcement("demo", 1, 5_000);
+        assert!(parse_announcement("pong/lobby/other/p1", &payload(&msg)).is_none());
+        assert!(parse_announcement("pong/lobby/demo/p2", &payload(&msg)).is_none());
+        assert!(parse_announcement("pong/lobby/demo/p3", &payload(&msg)).is_none());
+        assert!(parse_announcement("pong/lobby/demo/p1", b"not json").is_none());
+    }
+
+    #[test]
+    fn expire_drops_slots_that_stopped_refreshing() {
+        let mut browser = LobbyBrowser::default();
+        browser.apply(LobbyEvent::Announced(announcement("demo", 1, 0)), 10_000);
+        browser.apply(LobbyEvent::Announced(announcement("demo", 2, 0)), 40_000);
+
+        browser.expire(10_000 + LOBBY_TTL_MS);
+        assert_eq!(browser.games()[0].taken, [true, true]);
+
+        // P1 went quiet; P2 is still refreshing
+        browser.expire(10_001 + LOBBY_TTL_MS);
+        assert_eq!(browser.games()[0].taken, [false, true]);
+
+        browser.expire(40_001 + LOBBY_TTL_MS);
+        assert!(browser.games().is_empty());
+    }
+
+    #[test]
+    fn refresh_keeps_a_game_listed() {
+        let mut browser = LobbyBrowser::default();
+        browser.apply(LobbyEvent::Announced(announcement("demo", 1, 0)), 0);
+        let refresh = announcement("demo", 1, LOBBY_REFRESH_MS);
+        browser.apply(LobbyEvent::Announced(refresh), LOBBY_REFRESH_MS);
+        browser.expire(LOBBY_TTL_MS + 1);
+        assert_eq!(browser.games().len(), 1);
+    }
+
+    #[test]
+    fn expiry_ignores_the_announcers_clock() {
+        let now = 1_700_000_000_000;
+        let mut browser = LobbyBrowser::default();
+        // An hour slow and an hour fast: both are fresh when they arrive
+        let slow = announcement("slow", 1, now - 3_600_000);
+        let fast = announcement("fast", 1, now + 3_600_000);
+        browser.apply(LobbyEvent::Announced(slow), now);
+        browser.apply(LobbyEvent::Announced(fast), now);
+        browser.expire(now + LOBBY_TTL_MS);
+        assert_eq!(browser.games().len(), 2);
+        browser.expire(now + LOBBY_TTL_MS + 1);
+        assert!(browser.games().is_empty());
+    }
+}
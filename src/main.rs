@@ -1,5 +1,6 @@
 use std::{
     io::{self},
+    net::IpAddr,
     sync::mpsc,
     thread::sleep,
     time::{Duration, Instant},
@@ -17,14 +18,17 @@ use ratatui::{
 };
 use tui_big_text::{BigText, PixelSize};
 
-mod game;
-mod game_theme;
-mod helpers;
-mod network;
-use crate::{
-    game::{Game, GameType},
-    helpers::centered_rect_with_percentage,
-    network::{NetworkConfig, NetworkEvent},
+use clap::Parser;
+use rust_pong::{
+    broker,
+    cli::Cli,
+    config::{self, Config},
+    game::{Game, GameType, DEFAULT_TARGET_SCORE, PLAYER_NAME_CHAR_LEN},
+    helpers::{self, centered_rect_with_percentage, format_age, format_clock},
+    lobby::{self, LobbyBrowser, LobbyEvent, LobbySession},
+    matchmaker::{self, MatchEvent, MatchSession},
+    network::{self, BrokerEndpoint, NetworkConfig, NetworkEvent, Role, Transport},
+    replay::{self, ListedReplay, Recorder, Replay, ReplayPlayer},
 };
 
 #[derive(Debug)]
@@ -37,10 +41,16 @@ struct MainMenu {
 enum AppScreen {
     MainMenu,
     NetworkLobby,
+    LobbyBrowser,
+    QuickMatch,
+    LocalSetup,
     Game,
+    Replays,
+    ReplayViewer,
 }
 
-use crate::game_theme::GameTheme;
+use rust_pong::game_theme::GameTheme;
+use rust_pong::tls::TlsSettings;
 
 struct App {
     exit: bool,
@@ -54,13 +64,50 @@ struct App {
     network_local_player: u8,     // 1 or 2
     network_game_id: String,      // typed game ID
     network_player_select: u8,    // lobby: which player slot selected (1 or 2)
-    network_lobby_field: usize,   // 0=game_id, 1=player, 2=connect, 3=back
+    network_spectate: bool,       // lobby: watch instead of taking the selected slot
+    network_name: String,         // lobby: profile name sent to the opponent
+    network_lobby_field: usize,   // 0=game_id, 1=name, 2=player, 3=broker/port, 4=connect, 5=browse, 6=back
+    network_broker: String,       // lobby: "host:port" or ws:// / wss:// / mqtts:// URL, or just the port when hosting
+    network_hosting: bool,        // lobby opened via "Host Game (LAN)"
+    hosted_port: Option<u16>,     // embedded broker already running on this port
+    lan_address: Option<IpAddr>,  // lobby: address others join on, looked up when hosting starts
     network_last_paddle_y: f32,   // debounce: only publish when changed (physics units)
     network_status: NetworkStatus,
     network_serve_tx: Option<mpsc::SyncSender<()>>,
     network_restart_tx: Option<mpsc::SyncSender<()>>,
     network_ready_tx: Option<mpsc::SyncSender<()>>,
+    network_chat_tx: Option<mpsc::SyncSender<String>>,
+    recorder: Option<Recorder>,   // writing this game to a replay file
     game_over: bool,  // Track when game ends for overlay UI
+    // Lobby browser state
+    browser: Option<LobbySession>, // live while the browser screen is open
+    browser_games: LobbyBrowser,
+    browser_selected: usize,
+    browser_connected: bool,
+    // Quick Match state
+    quick_match: Option<MatchSession>, // our ticket, while queued
+    quick_match_connected: bool,
+    quick_match_since: Instant,
+    // Replays state
+    replays: Vec<ListedReplay>,   // files in the replays directory, newest first
+    replays_selected: usize,
+    replays_error: Option<String>, // listing or loading failed
+    replay: Option<ReplayPlayer>, // open in the viewer (drawn through current_game)
+    replay_last_frame: Instant,
+    // Local setup state
+    local_target_score: u32,      // first to this many points wins
+    local_setup_field: usize,     // index into local_setup_fields()
+    local_vs_cpu: bool,           // setup opened via "Play vs CPU"
+    local_difficulty: f32,        // CPU difficulty, 0.0 - 2.0
+    config: Config,               // settings for this run (file + env overrides)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LocalSetupField {
+    TargetScore,
+    Difficulty,
+    Start,
+    Back,
 }
 
 #[derive(Debug, PartialEq)]
@@ -68,17 +115,50 @@ enum NetworkStatus {
     Idle,
     Connecting,
     Connected,
+    Reconnecting(u32),
     Disconnected,
+    HostFailed(String),
+    /// The lobby's Broker field didn't parse
+    BadBroker(String),
+    /// The server gave our slot to someone else; the other slot if it's free
+    SlotTaken { requested: u8, free: Option<u8> },
+    TlsFailed(String),
+}
+
+impl NetworkStatus {
+    fn label(&self) -> String {
+        match self {
+            NetworkStatus::Idle => "Ready".to_string(),
+            NetworkStatus::Connecting => "Connecting...".to_string(),
+            NetworkStatus::Connected => "Connected".to_string(),
+            NetworkStatus::Reconnecting(n) => format!("Reconnecting (attempt {})", n),
+            NetworkStatus::Disconnected => "Disconnected - try again".to_string(),
+            NetworkStatus::HostFailed(e) => format!("Could not host: {}", e),
+            NetworkStatus::BadBroker(e) => format!("Bad broker address: {}", e),
+            NetworkStatus::TlsFailed(e) => format!("TLS setup failed: {}", e),
+            NetworkStatus::SlotTaken { requested, free: Some(free) } => {
+                format!("Player {} is taken - Player {} is free", requested, free)
+            }
+            NetworkStatus::SlotTaken { requested, free: None } => {
+                format!("Player {} is taken and the game is full - Spectate?", requested)
+            }
+        }
+    }
 }
 
-const MAIN_MENU_OPTIONS: [&str; 2] = [
+const MAIN_MENU_OPTIONS: [&str; 7] = [
     "Play Online (MQTT)",
+    "Quick Match",
+    "Host Game (LAN)",
+    "Local Versus",
+    "Play vs CPU",
+    "Replays",
     "Exit",
 ];
 const MENU_LAST_IDX: usize = MAIN_MENU_OPTIONS.len() - 1;
 
 impl App {
-    fn new() -> Self {
+    fn new(config: Config) -> Self {
         let main_menu = MainMenu {
             options: MAIN_MENU_OPTIONS.to_vec(),
             selected: 0,
@@ -86,22 +166,47 @@ impl App {
 
         Self {
             exit: false,
-            main_menu: main_menu,
+            main_menu,
             current_game: None,
             screen: AppScreen::MainMenu,
-            selected_theme: GameTheme::Monokai,
+            selected_theme: config.game.theme,
             network_rx: None,
             network_paddle_tx: None,
             network_local_player: 1,
-            network_game_id: String::from("demo"),
-            network_player_select: 1,
+            network_game_id: config.game.game_id.clone(),
+            network_player_select: config.game.player,
+            network_spectate: false,
+            network_name: config.profile_name(),
             network_lobby_field: 0,
+            network_broker: config.broker_address(),
+            network_hosting: false,
+            hosted_port: None,
+            lan_address: None,
             network_last_paddle_y: -1.0,
             network_status: NetworkStatus::Idle,
             network_serve_tx: None,
             network_restart_tx: None,
             network_ready_tx: None,
+            network_chat_tx: None,
+            recorder: None,
             game_over: false,
+            browser: None,
+            browser_games: LobbyBrowser::default(),
+            browser_selected: 0,
+            browser_connected: false,
+            quick_match: None,
+            quick_match_connected: false,
+            quick_match_since: Instant::now(),
+            replays: Vec::new(),
+            replays_selected: 0,
+            replays_error: None,
+            replay: None,
+            replay_last_frame: Instant::now(),
+            local_target_score: DEFAULT_TARGET_SCORE,
+            local_setup_field: 0,
+            local_vs_cpu: false,
+            local_difficulty: config.game.difficulty,
+            config,
         }
     }
 
@@ -135,6 +240,31 @@ impl App {
                         self.handle_network_lobby_events()?;
                         let _ = terminal.draw(|frame| self.draw_network_lobby(frame));
                     }
+                    AppScreen::LobbyBrowser => {
+                        self.drain_lobby_events();
+                        self.handle_lobby_browser_events()?;
+                        let _ = terminal.draw(|frame| self.draw_lobby_browser(frame));
+                    }
+                    AppScreen::QuickMatch => {
+                        self.drain_match_events();
+                        self.handle_quick_match_events()?;
+                        let _ = terminal.draw(|frame| self.draw_quick_match(frame));
+                    }
+                    AppScreen::LocalSetup => {
+                        self.handle_local_setup_events()?;
+                        let _ = terminal.draw(|frame| self.draw_local_setup(frame));
+                    }
+                    AppScreen::Replays => {
+                        self.handle_replays_events()?;
+                        let _ = terminal.draw(|frame| self.draw_replays(frame));
+                    }
+                    AppScreen::ReplayViewer => {
+                        self.handle_replay_viewer_events()?;
+                        self.update_replay_viewer();
+                        if let Some(game) = self.current_game.as_mut() {
+                            let _ = terminal.draw(|frame| game.draw(frame));
+                        }
+                    }
                     AppScreen::Game => {
                         let frame_start = Instant::now();
 
@@ -142,30 +272,31 @@ impl App {
                         self.drain_network_events();
 
                         // Handle game over input (Space to ready up)
-                        if self.game_over {
-                            if event::poll(Duration::from_millis(5))? {
-                                if let Event::Key(key_event) = event::read()? {
-                                    if key_event.kind == KeyEventKind::Press {
-                                        match key_event.code {
-                                            KeyCode::Char(' ') | KeyCode::Enter => {
-                                                if let Some(tx) = &self.network_ready_tx {
-                                                    tx.try_send(()).ok();
-                                                }
-                                            }
-                                            KeyCode::Esc => {
-                                                self.current_game = None;
-                                                self.network_rx = None;
-                                                self.network_paddle_tx = None;
-                                                self.network_serve_tx = None;
-                                                self.network_restart_tx = None;
-                                                self.network_ready_tx = None;
+                        if self.game_over && event::poll(Duration::from_millis(5))? {
+                            if let Event::Key(key_event) = event::read()? {
+                                let chat_used = key_event.kind == KeyEventKind::Press
+                                    && self
+                                        .current_game
+                                        .as_mut()
+                                        .is_some_and(|g| g.handle_chat_key(key_event.code));
+                                if key_event.kind == KeyEventKind::Press && !chat_used {
+                                    match key_event.code {
+                                        KeyCode::Char(' ') | KeyCode::Enter => {
+                                            if let Some(tx) = &self.network_ready_tx {
+                                                tx.try_send(()).ok();
+                                            } else if self.network_rx.is_some() {
+                                                // Spectating: the players decide on a rematch
+                                            } else if let Some(game) = self.current_game.as_mut() {
+                                                // Local match: rematch straight away
+                                                game.restart_match();
                                                 self.game_over = false;
-                                                self.network_status = NetworkStatus::Idle;
-                                                self.screen = AppScreen::MainMenu;
-                                                continue;
                                             }
-                                            _ => {}
                                         }
+                                        KeyCode::Esc => {
+                                            self.leave_game();
+                                            continue;
+                                        }
+                                        _ => {}
                                     }
                                 }
                             }
@@ -173,7 +304,13 @@ impl App {
 
                         let continue_game = if !self.game_over {
                             match self.current_game.as_mut() {
-                                Some(game) => game.game_loop()?,
+                                Some(game) => {
+                                    let keep_going = game.game_loop()?;
+                                    if game.is_game_over() {
+                                        self.game_over = true;
+                                    }
+                                    keep_going
+                                }
                                 None => false,
                             }
                         } else {
@@ -181,15 +318,7 @@ impl App {
                         };
 
                         if !continue_game {
-                            self.current_game = None;
-                            self.network_rx = None;
-                            self.network_paddle_tx = None;
-                            self.network_serve_tx = None;
-                            self.network_restart_tx = None;
-                            self.network_ready_tx = None;
-                            self.game_over = false;
-                            self.network_status = NetworkStatus::Idle;
-                            self.screen = AppScreen::MainMenu;
+                            self.leave_game();
                         } else {
                             // Check if player wants to serve
                             let wants_serve = self.current_game.as_ref().map(|g| g.pending_serve).unwrap_or(false);
@@ -202,6 +331,15 @@ impl App {
                                 }
                             }
 
+                            // Publish whatever was typed into the chat pane
+                            if let Some(game) = self.current_game.as_mut() {
+                                for line in game.take_chat_outbox() {
+                                    if let Some(tx) = &self.network_chat_tx {
+                                        tx.try_send(line).ok();
+                                    }
+                                }
+                            }
+
                             // Publish our paddle Y (physics units) if it changed
                             let local_idx = self.network_local_player.saturating_sub(1) as usize;
                             let paddle_y = self.current_game.as_ref().map(|g| g.get_paddle_physics_y(local_idx));
@@ -210,6 +348,9 @@ impl App {
                                     self.network_last_paddle_y = y;
                                     if let Some(tx) = &self.network_paddle_tx {
                                         tx.try_send(y).ok();
+                                        if let Some(recorder) = &mut self.recorder {
+                                            recorder.record_local_paddle(self.network_local_player, y);
+                                        }
                                     }
                                 }
                             }
@@ -220,6 +361,7 @@ impl App {
                                     if game_over {
                                         Self::draw_game_over_overlay(frame, game);
                                     }
+                                    game.draw_chat(frame);
                                 });
                             }
 
@@ -260,7 +402,7 @@ impl App {
             .direction(Direction::Vertical)
             .constraints(vec![
                 Constraint::Length(9),
-                Constraint::Length(9),
+                Constraint::Length(MAIN_MENU_OPTIONS.len() as u16 * 2 + 3),
                 Constraint::Max(5),
             ])
             .flex(Flex::Center)
@@ -297,7 +439,8 @@ impl App {
             .split(options_block_layout[0]);
 
         let inner_options_layout = options_layout[0].inner(Margin::new(1, 0));
-        let rows_stored = inner_options_layout.height.clamp(7, 20) as usize;
+        let rows_stored = (inner_options_layout.height.clamp(7, 20) as usize)
+            .max(self.main_menu.options.len() * 2 + 1);
 
         let option_constraints = vec![Constraint::Max(1); rows_stored];
         let option_areas = Layout::vertical(option_constraints)
@@ -355,11 +498,44 @@ impl App {
                                 0 => {
                                     // Play Online
                                     self.network_lobby_field = 0;
-                                    self.network_game_id = String::from("demo");
-                                    self.network_player_select = 1;
+                                    self.network_game_id = self.config.game.game_id.clone();
+                                    self.network_player_select = self.config.game.player;
+                                    self.network_spectate = false;
+                                    self.network_broker = self.config.broker_address();
+                                    self.network_hosting = false;
+                                    self.network_status = NetworkStatus::Idle;
+                                    self.screen = AppScreen::NetworkLobby;
+                                }
+                                1 => self.start_quick_match(),
+                                2 => {
+                                    // Host Game: embedded broker + server in this process
+                                    self.network_lobby_field = 0;
+                                    self.network_game_id = String::from("lan");
+                                    self.network_player_select = self.config.game.player;
+                                    self.network_spectate = false;
+                                    self.network_broker = self
+                                        .hosted_port
+                                        .unwrap_or(broker::DEFAULT_HOST_PORT)
+                                        .to_string();
+                                    self.network_hosting = true;
+                                    self.lan_address = broker::lan_address();
+                                    self.network_status = NetworkStatus::Idle;
                                     self.screen = AppScreen::NetworkLobby;
                                 }
-                                1 => {
+                                3 => {
+                                    // Local Versus: two players, one keyboard
+                                    self.local_setup_field = 0;
+                                    self.local_vs_cpu = false;
+                                    self.screen = AppScreen::LocalSetup;
+                                }
+                                4 => {
+                                    // Play vs CPU: human P1 against ComputerAI
+                                    self.local_setup_field = 0;
+                                    self.local_vs_cpu = true;
+                                    self.screen = AppScreen::LocalSetup;
+                                }
+                                5 => self.open_replays(),
+                                6 => {
                                     self.exit();
                                 }
                                 _ => {}
@@ -382,12 +558,7 @@ impl App {
         let area = frame.area();
         let popup_area = centered_rect_with_percentage(50, 40, area.width, area.height);
 
-        let status_label = match self.network_status {
-            NetworkStatus::Idle => "Ready",
-            NetworkStatus::Connecting => "Connecting...",
-            NetworkStatus::Connected => "Connected",
-            NetworkStatus::Disconnected => "Disconnected - try again",
-        };
+        let status_label = self.network_status.label();
 
         let field_labels = [
             format!(
@@ -395,8 +566,24 @@ impl App {
                 self.network_game_id,
                 if self.network_lobby_field == 0 { "_" } else { " " }
             ),
-            format!("Player:  {}", self.network_player_select),
-            "[ Connect ]".to_string(),
+            format!(
+                "Name:    {}{}",
+                self.network_name,
+                if self.network_lobby_field == 1 { "_" } else { " " }
+            ),
+            if self.network_spectate {
+                "Player:  Spectate".to_string()
+            } else {
+                format!("Player:  {}", self.network_player_select)
+            },
+            format!(
+                "{} {}{}",
+                if self.network_hosting { "Port:   " } else { "Broker: " },
+                self.network_broker,
+                if self.network_lobby_field == 3 { "_" } else { " " }
+            ),
+            if self.network_hosting { "[ Host    ]" } else { "[ Connect ]" }.to_string(),
+            "[ Browse  ]".to_string(),
             "[ Back    ]".to_string(),
         ];
 
@@ -404,6 +591,15 @@ impl App {
             format!(" Status: {}\n", status_label),
             String::new(),
         ];
+        if self.network_hosting {
+            let port = self.network_broker.trim();
+            let share = match self.lan_address {
+                Some(ip) => format!(" Others join with Broker: {}:{}\n", ip, port),
+                None => format!(" Others join with Broker: <your IP>:{}\n", port),
+            };
+            lines.push(share);
+            lines.push(String::new());
+        }
         for (i, label) in field_labels.iter().enumerate() {
             if i == self.network_lobby_field {
                 lines.push(format!(" > {} <\n", label));
@@ -413,13 +609,13 @@ impl App {
         }
         lines.push(String::new());
         lines.push(String::from(
-            " Tab/↑↓ navigate  ←/→ toggle player  Enter confirm  Esc back",
+            " Tab/↑↓ navigate  ←/→ player or spectate  Enter confirm  Esc back",
         ));
 
         let popup = Paragraph::new(lines.concat())
             .block(
                 Block::default()
-                    .title(" Play Online ")
+                    .title(if self.network_hosting { " Host Game " } else { " Play Online " })
                     .borders(Borders::ALL)
                     .border_type(BorderType::Double)
                     .style(Style::default().fg(Color::Cyan)),
@@ -438,40 +634,82 @@ impl App {
                             self.screen = AppScreen::MainMenu;
                         }
                         KeyCode::Tab | KeyCode::Down => {
-                            self.network_lobby_field = (self.network_lobby_field + 1) % 4;
+                            self.network_lobby_field = (self.network_lobby_field + 1) % 7;
                         }
                         KeyCode::Up => {
                             if self.network_lobby_field == 0 {
-                                self.network_lobby_field = 3;
+                                self.network_lobby_field = 6;
                             } else {
                                 self.network_lobby_field -= 1;
                             }
                         }
-                        KeyCode::Left | KeyCode::Right => {
-                            if self.network_lobby_field == 1 {
-                                self.network_player_select = if self.network_player_select == 1 { 2 } else { 1 };
-                            }
-                        }
-                        KeyCode::Backspace => {
-                            if self.network_lobby_field == 0 {
-                                self.network_game_id.pop();
+                        KeyCode::Right if self.network_lobby_field == 2 => {
+                            // 1 → 2 → Spectate → 1
+                            match (self.network_spectate, self.network_player_select) {
+                                (true, _) => {
+                                    self.network_spectate = false;
+                                    self.network_player_select = 1;
+                                }
+                                (false, 1) => self.network_player_select = 2,
+                                (false, _) => self.network_spectate = true,
                             }
                         }
-                        KeyCode::Char(c) => {
-                            if self.network_lobby_field == 0 {
-                                if self.network_game_id.len() < 20 && c.is_ascii_alphanumeric() {
-                                    self.network_game_id.push(c);
+                        KeyCode::Left if self.network_lobby_field == 2 => {
+                            match (self.network_spectate, self.network_player_select) {
+                                (true, _) => {
+                                    self.network_spectate = false;
+                                    self.network_player_select = 2;
                                 }
+                                (false, 2) => self.network_player_select = 1,
+                                (false, _) => self.network_spectate = true,
                             }
                         }
+                        KeyCode::Backspace if self.network_lobby_field == 0 => {
+                            self.network_game_id.pop();
+                        }
+                        KeyCode::Backspace if self.network_lobby_field == 1 => {
+                            self.network_name.pop();
+                        }
+                        KeyCode::Backspace if self.network_lobby_field == 3 => {
+                            self.network_broker.pop();
+                        }
+                        KeyCode::Char(c)
+                            if self.network_lobby_field == 0
+                                && self.network_game_id.len() < 20
+                                && c.is_ascii_alphanumeric() =>
+                        {
+                            self.network_game_id.push(c);
+                        }
+                        KeyCode::Char(c)
+                            if self.network_lobby_field == 1
+                                && self.network_name.chars().count() < PLAYER_NAME_CHAR_LEN
+                                && (c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.')) =>
+                        {
+                            self.network_name.push(c);
+                        }
+                        KeyCode::Char(c)
+                            if self.network_lobby_field == 3
+                                && self.network_broker.len() < 64
+                                && (c.is_ascii_digit()
+                                    || (!self.network_hosting
+                                        && (c.is_ascii_alphanumeric()
+                                            || matches!(c, '.' | '-' | '_' | ':' | '/')))) =>
+                        {
+                            self.network_broker.push(c);
+                        }
                         KeyCode::Enter => {
                             match self.network_lobby_field {
-                                3 => {
+                                6 => {
                                     // Back
                                     self.screen = AppScreen::MainMenu;
                                 }
-                                2 | _ => {
+                                5 => self.open_lobby_browser(),
+                                _ => {
                                     // Connect
+                                    if !self.network_hosting {
+                                        self.remember_lobby_choices();
+                                    }
+                                    self.remember_profile_name();
                                     self.launch_network_game();
                                 }
                             }
@@ -485,6 +723,558 @@ impl App {
         Ok(())
     }
 
+    // -----------------------------------------------------------------------
+    // Lobby browser (games announced on the lobby's broker)
+    // -----------------------------------------------------------------------
+
+    fn open_lobby_browser(&mut self) {
+        let Some(config) = self.lobby_network_config(String::new(), Role::spectator()) else {
+            return;
+        };
+        match lobby::browse(&config) {
+            Ok(session) => {
+                self.browser = Some(session);
+                self.browser_games = LobbyBrowser::default();
+                self.browser_selected = 0;
+                self.browser_connected = false;
+                self.screen = AppScreen::LobbyBrowser;
+            }
+            Err(e) => self.network_status = NetworkStatus::TlsFailed(e.to_string()),
+        }
+    }
+
+    fn close_lobby_browser(&mut self) {
+        self.browser = None;
+        self.screen = AppScreen::NetworkLobby;
+    }
+
+    fn drain_lobby_events(&mut self) {
+        let Some(session) = &self.browser else {
+            return;
+        };
+        while let Ok(event) = session.rx.try_recv() {
+            match event {
+                LobbyEvent::Connected => self.browser_connected = true,
+                LobbyEvent::Disconnected => self.browser_connected = false,
+                event => self.browser_games.apply(event, network::now_ms()),
+            }
+        }
+        self.browser_games.expire(network::now_ms());
+    }
+
+    fn draw_lobby_browser(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = centered_rect_with_percentage(70, 60, area.width, area.height);
+
+        let games = self.browser_games.games();
+        self.browser_selected = self.browser_selected.min(games.len().saturating_sub(1));
+        let now = network::now_ms();
+
+        let status = if !self.browser_connected {
+            "Connecting..."
+        } else if games.is_empty() {
+            "No games yet - start one from the lobby and it shows up here"
+        } else {
+            "Connected"
+        };
+        let mut lines = vec![
+            format!(" Broker: {}\n", self.network_broker.trim()),
+            format!(" Status: {}\n", status),
+            String::new(),
+            format!(
+                "   {:<20} {:<16} {:<8} {:<9} {}\n",
+                "Game", "Host", "Players", "Status", "Age"
+            ),
+        ];
+        for (i, game) in games.iter().enumerate() {
+            let status = match game.status {
+                network::GameStatus::Waiting => "waiting",
+                network::GameStatus::Playing => "playing",
+                network::GameStatus::Ended => "ended",
+            };
+            let row = format!(
+                "{:<20} {:<16} {:<8} {:<9} {}",
+                game.game_id,
+                game.host,
+                format!("{}/2", game.players()),
+                status,
+                format_age(now.saturating_sub(game.since)),
+            );
+            if i == self.browser_selected {
+                lines.push(format!(" > {} <\n", row));
+            } else {
+                lines.push(format!("   {}\n", row));
+            }
+        }
+        lines.push(String::new());
+        lines.push(String::from(
+            " ↑↓ select  Enter join (full games: watch)  Esc back",
+        ));
+
+        let popup = Paragraph::new(lines.concat())
+            .block(
+                Block::default()
+                    .title(" Open Games ")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .style(Style::default().fg(Color::Cyan)),
+            )
+            .style(Style::default().fg(Color::Green))
+            .alignment(Alignment::Left);
+        frame.render_widget(popup, popup_area);
+    }
+
+    fn handle_lobby_browser_events(&mut self) -> io::Result<()> {
+        if event::poll(Duration::from_millis(10))? {
+            if let Event::Key(key_event) = event::read()? {
+                if key_event.kind != KeyEventKind::Press {
+                    return Ok(());
+                }
+                let games = self.browser_games.games();
+                match key_event.code {
+                    KeyCode::Esc => self.close_lobby_browser(),
+                    KeyCode::Up => self.browser_selected = self.browser_selected.saturating_sub(1),
+                    KeyCode::Down | KeyCode::Tab if self.browser_selected + 1 < games.len() => {
+                        self.browser_selected += 1;
+                    }
+                    KeyCode::Enter => {
+                        if let Some(game) = games.get(self.browser_selected) {
+                            // Take a free slot, or watch a full game
+                            self.network_game_id = game.game_id.clone();
+                            match game.free_slot() {
+                                Some(slot) => {
+                                    self.network_player_select = slot;
+                                    self.network_spectate = false;
+                                }
+                                None => self.network_spectate = true,
+                            }
+                            self.close_lobby_browser();
+                            self.launch_network_game();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // -----------------------------------------------------------------------
+    // Quick Match (queue on the configured broker until paired)
+    // -----------------------------------------------------------------------
+
+    fn start_quick_match(&mut self) {
+        self.network_hosting = false;
+        self.network_broker = self.config.broker_address();
+        self.network_spectate = false;
+        self.network_status = NetworkStatus::Idle;
+        let Some(config) = self.lobby_network_config(String::new(), Role::spectator()) else {
+            return;
+        };
+        match matchmaker::queue(&config, self.config.game.skill) {
+            Ok(session) => {
+                self.quick_match = Some(session);
+                self.quick_match_connected = false;
+                self.quick_match_since = Instant::now();
+            }
+            Err(e) => self.network_status = NetworkStatus::TlsFailed(e.to_string()),
+        }
+        self.screen = AppScreen::QuickMatch;
+    }
+
+    fn drain_match_events(&mut self) {
+        let Some(session) = &self.quick_match else {
+            return;
+        };
+        while let Ok(event) = session.rx.try_recv() {
+            match event {
+                MatchEvent::Connected => self.quick_match_connected = true,
+                MatchEvent::Disconnected => self.quick_match_connected = false,
+                MatchEvent::Matched(pairing) => {
+                    // Same path as joining from the lobby, with the slot we were given
+                    self.quick_match = None;
+                    self.network_game_id = pairing.game_id;
+                    self.network_player_select = pairing.player;
+                    self.network_spectate = false;
+                    self.launch_network_game();
+                    return;
+                }
+            }
+        }
+    }
+
+    fn draw_quick_match(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = centered_rect_with_percentage(50, 30, area.width, area.height);
+
+        let status = match &self.quick_match {
+            None => self.network_status.label(),
+            Some(_) if !self.quick_match_connected => "Connecting...".to_string(),
+            Some(_) => format!(
+                "Looking for an opponent ({}s)",
+                self.quick_match_since.elapsed().as_secs()
+            ),
+        };
+        let lines = [
+            format!(" Broker: {}\n", self.network_broker.trim()),
+            format!(" Status: {}\n", status),
+            String::new(),
+            String::from(" You'll be put in a game as soon as someone else queues\n"),
+            String::new(),
+            String::from(" Esc cancel"),
+        ];
+
+        let popup = Paragraph::new(lines.concat())
+            .block(
+                Block::default()
+                    .title(" Quick Match ")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .style(Style::default().fg(Color::Cyan)),
+            )
+            .style(Style::default().fg(Color::Green))
+            .alignment(Alignment::Left);
+        frame.render_widget(popup, popup_area);
+    }
+
+    fn handle_quick_match_events(&mut self) -> io::Result<()> {
+        if event::poll(Duration::from_millis(10))? {
+            if let Event::Key(key_event) = event::read()? {
+                if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Esc {
+                    // Dropping the session takes our ticket off the queue
+                    self.quick_match = None;
+                    self.screen = AppScreen::MainMenu;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // -----------------------------------------------------------------------
+    // Replays (recorded with --record, played back through Game::draw)
+    // -----------------------------------------------------------------------
+
+    fn open_replays(&mut self) {
+        match replay::list_replays() {
+            Ok(replays) => {
+                self.replays = replays;
+                self.replays_error = None;
+            }
+            Err(e) => {
+                self.replays = Vec::new();
+                self.replays_error = Some(e.to_string());
+            }
+        }
+        self.replays_selected = self.replays_selected.min(self.replays.len().saturating_sub(1));
+        self.screen = AppScreen::Replays;
+    }
+
+    fn draw_replays(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = centered_rect_with_percentage(70, 60, area.width, area.height);
+        let now = network::now_ms();
+
+        let status = match &self.replays_error {
+            Some(e) => e.clone(),
+            None if self.replays.is_empty() => {
+                "No replays yet - play online with --record (or record = true)".to_string()
+            }
+            None => format!("{} recorded", self.replays.len()),
+        };
+        let dir = replay::replays_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_else(|| "(no data directory)".to_string());
+        let mut lines = vec![
+            format!(" Folder: {}\n", dir),
+            format!(" Status: {}\n", status),
+            String::new(),
+            format!(
+                "   {:<20} {:<16} {:<10} {}\n",
+                "Game", "Name", "Played as", "Age"
+            ),
+        ];
+        for (i, listed) in self.replays.iter().enumerate() {
+            let header = &listed.header;
+            let role = match header.player {
+                Some(player) => format!("Player {}", player),
+                None => "Spectator".to_string(),
+            };
+            let row = format!(
+                "{:<20} {:<16} {:<10} {}",
+                header.game_id,
+                header.name,
+                role,
+                format_age(now.saturating_sub(header.started)),
+            );
+            if i == self.replays_selected {
+                lines.push(format!(" > {} <\n", row));
+            } else {
+                lines.push(format!("   {}\n", row));
+            }
+        }
+        lines.push(String::new());
+        lines.push(String::from(" ↑↓ select  Enter watch  Esc back"));
+
+        let popup = Paragraph::new(lines.concat())
+            .block(
+                Block::default()
+                    .title(" Replays ")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .style(Style::default().fg(Color::Cyan)),
+            )
+            .style(Style::default().fg(Color::Green))
+            .alignment(Alignment::Left);
+        frame.render_widget(popup, popup_area);
+    }
+
+    fn handle_replays_events(&mut self) -> io::Result<()> {
+        if event::poll(Duration::from_millis(10))? {
+            if let Event::Key(key_event) = event::read()? {
+                if key_event.kind != KeyEventKind::Press {
+                    return Ok(());
+                }
+                match key_event.code {
+                    KeyCode::Esc => self.screen = AppScreen::MainMenu,
+                    KeyCode::Up => self.replays_selected = self.replays_selected.saturating_sub(1),
+                    KeyCode::Down | KeyCode::Tab if self.replays_selected + 1 < self.replays.len() => {
+                        self.replays_selected += 1;
+                    }
+                    KeyCode::Enter => {
+                        if let Some(listed) = self.replays.get(self.replays_selected) {
+                            match Replay::load(&listed.path) {
+                                Ok(replay) => self.open_replay_viewer(replay),
+                                Err(e) => self.replays_error = Some(e.to_string()),
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn open_replay_viewer(&mut self, replay: Replay) {
+        // Recorded names come with the state messages; until then, ours from the header
+        let mut names = ["Player 1".to_string(), "Player 2".to_string()];
+        if let Some(player) = replay.header.player.filter(|p| (1..=2).contains(p)) {
+            names[player as usize - 1] = replay.header.name.clone();
+        }
+        let mut game = Game::new(
+            [names[0].as_str(), names[1].as_str()],
+            Rect::default(),
+            GameType::WithNetwork,
+            Some(1.0),
+        );
+        game.set_theme(self.selected_theme);
+        game.set_spectator();
+        self.current_game = Some(game);
+        self.replay = Some(ReplayPlayer::new(replay));
+        self.replay_last_frame = Instant::now();
+        self.screen = AppScreen::ReplayViewer;
+    }
+
+    fn close_replay_viewer(&mut self) {
+        self.current_game = None;
+        self.replay = None;
+        self.screen = AppScreen::Replays;
+    }
+
+    /// Move the playback clock on and hand the frame to the game for drawing
+    fn update_replay_viewer(&mut self) {
+        let elapsed = self.replay_last_frame.elapsed();
+        self.replay_last_frame = Instant::now();
+        let (Some(player), Some(game)) = (self.replay.as_mut(), self.current_game.as_mut()) else {
+            return;
+        };
+        player.advance(elapsed);
+        let status = format!(
+            "{} {}x  {} / {}  Rally {}/{}  |  Space pause  ←/→ frame  -/+ speed  [/] rally  {{/}} point  Esc back",
+            if player.is_paused() { "❚❚" } else { "▶" },
+            player.speed(),
+            format_clock(player.position_ms()),
+            format_clock(player.duration_ms()),
+            player.rally(),
+            player.replay.rallies(),
+        );
+        game.show_replay_frame(&player.frame(), status);
+    }
+
+    fn handle_replay_viewer_events(&mut self) -> io::Result<()> {
+        if event::poll(Duration::from_millis(10))? {
+            if let Event::Key(key_event) = event::read()? {
+                if key_event.kind != KeyEventKind::Press {
+                    return Ok(());
+                }
+                if key_event.code == KeyCode::Esc {
+                    self.close_replay_viewer();
+                    return Ok(());
+                }
+                let Some(player) = self.replay.as_mut() else {
+                    return Ok(());
+                };
+                match key_event.code {
+                    KeyCode::Char(' ') => player.toggle_pause(),
+                    KeyCode::Char('+') | KeyCode::Char('=') => player.faster(),
+                    KeyCode::Char('-') => player.slower(),
+                    KeyCode::Right => player.step(true),
+                    KeyCode::Left => player.step(false),
+                    KeyCode::Char(']') => player.seek_rally(true),
+                    KeyCode::Char('[') => player.seek_rally(false),
+                    KeyCode::Char('}') => player.seek_point(true),
+                    KeyCode::Char('{') => player.seek_point(false),
+                    KeyCode::Home => player.restart(),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // -----------------------------------------------------------------------
+    // Local setup (Local Versus)
+    // -----------------------------------------------------------------------
+
+    fn local_setup_fields(&self) -> &'static [LocalSetupField] {
+        if self.local_vs_cpu {
+            &[
+                LocalSetupField::TargetScore,
+                LocalSetupField::Difficulty,
+                LocalSetupField::Start,
+                LocalSetupField::Back,
+            ]
+        } else {
+            &[
+                LocalSetupField::TargetScore,
+                LocalSetupField::Start,
+                LocalSetupField::Back,
+            ]
+        }
+    }
+
+    fn draw_local_setup(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+        let popup_area = centered_rect_with_percentage(50, 40, area.width, area.height);
+
+        let diff_label = match self.local_difficulty {
+            d if d < 0.6 => "Easy",
+            d if d < 1.3 => "Normal",
+            _ => "Hard",
+        };
+
+        let mut lines = vec![
+            if self.local_vs_cpu {
+                " You: ↑/W ↓/S move, Space power  |  CPU on the right\n".to_string()
+            } else {
+                " P1: ↑/↓ move, / power  |  P2: W/S move, Space power\n".to_string()
+            },
+            String::new(),
+        ];
+        for (i, field) in self.local_setup_fields().iter().enumerate() {
+            let label = match field {
+                LocalSetupField::TargetScore => format!("First to: {} points", self.local_target_score),
+                LocalSetupField::Difficulty => {
+                    format!("CPU:      {} ({:.1})", diff_label, self.local_difficulty)
+                }
+                LocalSetupField::Start => "[ Start ]".to_string(),
+                LocalSetupField::Back => "[ Back  ]".to_string(),
+            };
+            if i == self.local_setup_field {
+                lines.push(format!(" > {} <\n", label));
+            } else {
+                lines.push(format!("   {}\n", label));
+            }
+        }
+        lines.push(String::new());
+        lines.push(String::from(
+            " Tab/↑↓ navigate  ←/→ adjust  Enter confirm  Esc back",
+        ));
+
+        let popup = Paragraph::new(lines.concat())
+            .block(
+                Block::default()
+                    .title(if self.local_vs_cpu { " Play vs CPU " } else { " Local Versus " })
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .style(Style::default().fg(Color::Cyan)),
+            )
+            .style(Style::default().fg(Color::Green))
+            .alignment(Alignment::Left);
+        frame.render_widget(popup, popup_area);
+    }
+
+    fn handle_local_setup_events(&mut self) -> io::Result<()> {
+        if event::poll(Duration::from_millis(10))? {
+            match event::read()? {
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    let fields = self.local_setup_fields();
+                    let field = fields[self.local_setup_field.min(fields.len() - 1)];
+                    match key_event.code {
+                        KeyCode::Esc => {
+                            self.screen = AppScreen::MainMenu;
+                        }
+                        KeyCode::Tab | KeyCode::Down => {
+                            self.local_setup_field = (self.local_setup_field + 1) % fields.len();
+                        }
+                        KeyCode::Up => {
+                            if self.local_setup_field == 0 {
+                                self.local_setup_field = fields.len() - 1;
+                            } else {
+                                self.local_setup_field -= 1;
+                            }
+                        }
+                        KeyCode::Left if field == LocalSetupField::TargetScore => {
+                            self.local_target_score = self.local_target_score.saturating_sub(1).max(1);
+                        }
+                        KeyCode::Right if field == LocalSetupField::TargetScore => {
+                            self.local_target_score = (self.local_target_score + 1).min(21);
+                        }
+                        KeyCode::Left if field == LocalSetupField::Difficulty => {
+                            self.local_difficulty = (self.local_difficulty - 0.1).clamp(0.0, 2.0);
+                        }
+                        KeyCode::Right if field == LocalSetupField::Difficulty => {
+                            self.local_difficulty = (self.local_difficulty + 0.1).clamp(0.0, 2.0);
+                        }
+                        KeyCode::Enter => match field {
+                            LocalSetupField::Back => self.screen = AppScreen::MainMenu,
+                            _ => self.launch_local_game(),
+                        },
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn launch_local_game(&mut self) {
+        // Local physics runs before the first draw, so start with the real play area
+        // (terminal minus the 3-row controls block) rather than Rect::default().
+        let (cols, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+        let area = Rect::new(0, 0, cols, rows.saturating_sub(3));
+
+        let mut game = if self.local_vs_cpu {
+            Game::new(["You", "CPU"], area, GameType::VsComputer, Some(self.local_difficulty))
+        } else {
+            Game::new(["Player 1", "Player 2"], area, GameType::LocalVersus, Some(1.0))
+        };
+        game.set_theme(self.selected_theme);
+        game.set_key_bindings(self.config.keys.resolve());
+        game.set_target_score(self.local_target_score);
+        game.set_spin(self.config.game.spin);
+        game.set_ramp(self.config.game.ramp);
+        if self.local_vs_cpu && self.local_difficulty != self.config.game.difficulty {
+            let difficulty = self.local_difficulty;
+            self.config.game.difficulty = difficulty;
+            config::update(|c| c.game.difficulty = difficulty);
+        }
+        self.game_over = false;
+        self.current_game = Some(game);
+        self.screen = AppScreen::Game;
+    }
+
     fn draw_game_over_overlay(frame: &mut Frame, game: &Game) {
         use helpers::centered_rect;
 
@@ -493,15 +1283,35 @@ impl App {
 
         // Determine winner
         let (p1_score, p2_score) = game.get_scores();
-        let winner_text = if p1_score > p2_score {
-            "Player 1 Wins!"
-        } else {
-            "Player 2 Wins!"
+        let winner = game.get_player(if p1_score > p2_score { 0 } else { 1 });
+        let winner_name = winner.name.iter().collect::<String>();
+        let winner_text = match winner_name.trim() {
+            "You" => "You Win!".to_string(),
+            name => format!("{} Wins!", name),
         };
+        let names = [0, 1].map(|i| game.get_player(i).name.iter().collect::<String>());
 
+        let prompt = if game.is_spectating() {
+            "Waiting for the players to ready up"
+        } else if game.is_networked() {
+            "Press SPACE to ready up"
+        } else {
+            "Press SPACE for a rematch"
+        };
+        let chat_hint = if game.is_networked() {
+            "\nT chat  1 gg  2 nice shot  3 rematch?"
+        } else {
+            ""
+        };
         let text = format!(
-            "{}\n\n{} - {}\n\nPress SPACE to ready up\nEsc to quit",
-            winner_text, p1_score, p2_score
+            "{}\n\n{} {} - {} {}\n\n{}\nEsc to quit{}",
+            winner_text,
+            names[0].trim(),
+            p1_score,
+            p2_score,
+            names[1].trim(),
+            prompt,
+            chat_hint
         );
 
         let popup = Paragraph::new(text)
@@ -518,31 +1328,160 @@ impl App {
         frame.render_widget(popup, popup_area);
     }
 
+    /// Save the lobby's Game ID and Player so they are pre-filled next time
+    fn remember_lobby_choices(&mut self) {
+        let game_id = self.network_game_id.trim().to_string();
+        let player = self.network_player_select;
+        if game_id.is_empty()
+            || (game_id == self.config.game.game_id && player == self.config.game.player)
+        {
+            return;
+        }
+        self.config.game.game_id = game_id.clone();
+        self.config.game.player = player;
+        config::update(|c| {
+            c.game.game_id = game_id;
+            c.game.player = player;
+        });
+    }
+
+    /// Save the lobby's Name (an empty one means the OS user name again)
+    fn remember_profile_name(&mut self) {
+        let name = self.network_name.trim().to_string();
+        if name == self.config.profile_name() {
+            return;
+        }
+        self.config.game.name = name.clone();
+        config::update(|c| c.game.name = name);
+        self.network_name = self.config.profile_name();
+    }
+
+    /// Command line: join the configured broker/game directly, skipping menu and lobby
+    fn launch_cli_network_game(&mut self) {
+        self.network_hosting = false;
+        self.network_broker = self.config.broker_address();
+        self.network_game_id = self.config.game.game_id.clone();
+        self.network_player_select = self.config.game.player;
+        self.network_spectate = false;
+        self.launch_network_game();
+    }
+
+    /// Command line: AI-vs-AI demo for kiosks. Endless; Esc returns to the menu.
+    fn launch_screensaver(&mut self) {
+        let (cols, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+        let area = Rect::new(0, 0, cols, rows.saturating_sub(3));
+
+        let mut game = Game::new(["CPU 1", "CPU 2"], area, GameType::ScreenSaver, Some(self.local_difficulty));
+        game.set_theme(self.selected_theme);
+        game.set_spin(self.config.game.spin);
+        game.set_ramp(self.config.game.ramp);
+        self.game_over = false;
+        self.current_game = Some(game);
+        self.screen = AppScreen::Game;
+    }
+
+    /// Connection settings for the broker in the lobby's Broker/Port field, starting the
+    /// embedded broker first when hosting. None (with the lobby status set) if that fails.
+    fn lobby_network_config(&mut self, game_id: String, role: Role) -> Option<NetworkConfig> {
+        let defaults = &self.config.broker;
+        let endpoint = if self.network_hosting {
+            let port = self
+                .network_broker
+                .trim()
+                .parse()
+                .unwrap_or(broker::DEFAULT_HOST_PORT);
+            if self.hosted_port != Some(port) {
+                if let Err(e) = broker::host(port, self.config.game.spin) {
+                    self.network_status = NetworkStatus::HostFailed(e.to_string());
+                    return None;
+                }
+                self.hosted_port = Some(port);
+            }
+            BrokerEndpoint {
+                host: "127.0.0.1".to_string(),
+                port,
+                transport: Transport::Tcp,
+                ws_path: String::new(),
+                tls: false,
+            }
+        } else {
+            if self.network_broker.trim().is_empty() {
+                self.config.broker_endpoint()
+            } else {
+                match BrokerEndpoint::parse(&self.network_broker, defaults.port) {
+                    Ok(endpoint) => endpoint,
+                    Err(e) => {
+                        self.network_status = NetworkStatus::BadBroker(e);
+                        return None;
+                    }
+                }
+            }
+        };
+
+        // Configured credentials and TLS files only belong to the configured broker;
+        // LAN brokers run plain TCP without auth
+        let (username, password, mut tls) = if endpoint.host == defaults.host {
+            (defaults.username.clone(), defaults.password.clone(), defaults.tls.clone())
+        } else {
+            (None, None, TlsSettings::default())
+        };
+        tls.enabled = endpoint.tls;
+
+        Some(NetworkConfig {
+            broker_host: endpoint.host,
+            broker_port: endpoint.port,
+            transport: endpoint.transport,
+            ws_path: endpoint.ws_path,
+            game_id,
+            role,
+            name: self.config.profile_name(),
+            ramp: self.config.game.ramp,
+            username,
+            password,
+            tls,
+        })
+    }
+
     fn launch_network_game(&mut self) {
         let game_id = if self.network_game_id.trim().is_empty() {
             "demo".to_string()
         } else {
             self.network_game_id.trim().to_string()
         };
+        let role = if self.network_spectate {
+            Role::spectator()
+        } else {
+            Role::Player(self.network_player_select)
+        };
+        let Some(config) = self.lobby_network_config(game_id.clone(), role) else {
+            return;
+        };
 
+        let handle = match network::connect(config) {
+            Ok(handle) => handle,
+            Err(e) => {
+                self.network_status = NetworkStatus::TlsFailed(e.to_string());
+                return;
+            }
+        };
         self.network_local_player = self.network_player_select;
         self.network_status = NetworkStatus::Connecting;
-
-        let config = NetworkConfig {
-            game_id: game_id.clone(),
-            player: self.network_local_player,
-            ..NetworkConfig::default()
-        };
-
-        let handle = network::connect(config);
         self.network_rx = Some(handle.rx);
-        self.network_paddle_tx = Some(handle.paddle_tx);
-        self.network_serve_tx = Some(handle.serve_tx);
-        self.network_restart_tx = Some(handle.restart_tx);
-        self.network_ready_tx = Some(handle.ready_tx);
+        self.network_chat_tx = Some(handle.chat_tx);
+        if !self.network_spectate {
+            self.network_paddle_tx = Some(handle.paddle_tx);
+            self.network_serve_tx = Some(handle.serve_tx);
+            self.network_restart_tx = Some(handle.restart_tx);
+            self.network_ready_tx = Some(handle.ready_tx);
+        }
 
-        let p1_name = if self.network_local_player == 1 { "You" } else { "Opponent" };
-        let p2_name = if self.network_local_player == 2 { "You" } else { "Opponent" };
+        // Remote names arrive with the server's state messages
+        let me = self.config.profile_name();
+        let (p1_name, p2_name) = match self.network_local_player {
+            _ if self.network_spectate => ("Player 1", "Player 2"),
+            1 => (me.as_str(), "Opponent"),
+            _ => ("Opponent", me.as_str()),
+        };
 
         let mut game = Game::new(
             [p1_name, p2_name],
@@ -551,8 +1490,26 @@ impl App {
             Some(1.0),
         );
         game.set_theme(self.selected_theme);
-        game.set_local_player_index((self.network_local_player - 1) as usize);
+        game.set_key_bindings(self.config.keys.resolve());
+        if self.network_spectate {
+            game.set_spectator();
+        } else {
+            game.set_local_player_index((self.network_local_player - 1) as usize);
+        }
+        game.set_smoothing(&self.config.smoothing);
+        game.set_traffic_counters(handle.traffic);
+        game.enable_chat();
         self.current_game = Some(game);
+        // Best effort: a game that can't be recorded is still played
+        self.recorder = self
+            .config
+            .game
+            .record
+            .then(|| {
+                let player = (!self.network_spectate).then_some(self.network_local_player);
+                Recorder::create(&game_id, player, &self.config.profile_name()).ok()
+            })
+            .flatten();
         self.screen = AppScreen::Game;
     }
 
@@ -561,56 +1518,140 @@ impl App {
     // -----------------------------------------------------------------------
 
     fn drain_network_events(&mut self) {
-        let local = self.network_local_player;
-        let opponent_idx = if local == 1 { 1 } else { 0 };
-
+        let mut slot_taken = None;
         if let Some(rx) = &self.network_rx {
             // drain all pending events without blocking
-            loop {
-                match rx.try_recv() {
-                    Ok(event) => match event {
-                        NetworkEvent::Connected => {
-                            self.network_status = NetworkStatus::Connected;
-                        }
-                        NetworkEvent::Disconnected => {
-                            self.network_status = NetworkStatus::Disconnected;
-                        }
-                        NetworkEvent::OpponentPaddle(y) => {
-                            if let Some(game) = &mut self.current_game {
-                                game.set_opponent_paddle(opponent_idx, y);
-                            }
+            while let Ok(event) = rx.try_recv() {
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record_event(&event);
+                }
+                match event {
+                    NetworkEvent::Connected => {
+                        self.network_status = NetworkStatus::Connected;
+                        if let Some(game) = &mut self.current_game {
+                            game.set_connection_banner(None);
                         }
-                        NetworkEvent::BallUpdate(b) => {
-                            if let Some(game) = &mut self.current_game {
-                                game.set_ball_from_network(b.x, b.y, b.dx, b.dy);
-                            }
+                    }
+                    NetworkEvent::Reconnecting(attempt) => {
+                        // Never connected yet: keep saying "Connecting"
+                        let banner = if self.network_status == NetworkStatus::Connecting {
+                            format!("Connecting (attempt {})", attempt + 1)
+                        } else {
+                            self.network_status = NetworkStatus::Reconnecting(attempt);
+                            format!("Reconnecting (attempt {})", attempt)
+                        };
+                        if let Some(game) = &mut self.current_game {
+                            game.set_connection_banner(Some(banner));
                         }
-                        NetworkEvent::StateUpdate(s) => {
-                            if let Some(game) = &mut self.current_game {
-                                game.set_scores(s.p1_score, s.p2_score);
-                            }
-                            // Track game over state for UI overlay
-                            if s.status == network::GameStatus::Ended {
-                                self.game_over = true;
-                            } else if s.status == network::GameStatus::Playing {
-                                self.game_over = false;
-                            }
+                    }
+                    NetworkEvent::Disconnected => {
+                        self.network_status = NetworkStatus::Disconnected;
+                        if let Some(game) = &mut self.current_game {
+                            game.set_connection_banner(Some("Connection lost".to_string()));
                         }
-                    },
-                    Err(_) => break, // channel empty or closed
+                    }
+                    NetworkEvent::Paddle(player, p) => {
+                        if let Some(game) = &mut self.current_game {
+                            game.set_remote_paddle(player.saturating_sub(1) as usize, &p);
+                        }
+                    }
+                    NetworkEvent::BallUpdate(b) => {
+                        if let Some(game) = &mut self.current_game {
+                            game.set_ball_from_network(&b);
+                        }
+                    }
+                    NetworkEvent::StateUpdate(s) => {
+                        if let Some(game) = &mut self.current_game {
+                            game.set_scores(s.p1_score, s.p2_score);
+                            game.set_rally(s.rally);
+                            game.set_remote_names([s.p1_name.as_deref(), s.p2_name.as_deref()]);
+                        }
+                        // Track game over state for UI overlay
+                        if s.status == network::GameStatus::Ended {
+                            self.game_over = true;
+                        } else if s.status == network::GameStatus::Playing {
+                            self.game_over = false;
+                        }
+                    }
+                    NetworkEvent::ClockSync(sync) => {
+                        if let Some(game) = &mut self.current_game {
+                            game.set_clock_sync(sync);
+                        }
+                    }
+                    NetworkEvent::Chat(msg) => {
+                        if let Some(game) = &mut self.current_game {
+                            game.receive_chat(&msg);
+                        }
+                    }
+                    NetworkEvent::SlotTaken { requested, free } => {
+                        slot_taken = Some((requested, free));
+                        break;
+                    }
                 }
             }
+            if let Some(game) = &mut self.current_game {
+                game.set_connection_status(&self.network_status.label());
+            }
+        }
+
+        // Back to the lobby with the error, the free slot (or Spectate) picked
+        // and Connect highlighted
+        if let Some((requested, free)) = slot_taken {
+            self.leave_game();
+            match free {
+                Some(slot) => self.network_player_select = slot,
+                None => self.network_spectate = true,
+            }
+            self.network_lobby_field = 4;
+            self.network_status = NetworkStatus::SlotTaken { requested, free };
+            self.screen = AppScreen::NetworkLobby;
         }
     }
 
+    /// Tear down the current game (and its network session) and go back to the menu.
+    /// A theme picked from the pause menu sticks for later games and is saved.
+    fn leave_game(&mut self) {
+        if let Some(theme) = self.current_game.as_ref().map(|g| g.get_theme()) {
+            if theme != self.selected_theme {
+                self.selected_theme = theme;
+                config::update(|c| c.game.theme = theme);
+            }
+        }
+        self.current_game = None;
+        self.network_rx = None;
+        self.network_paddle_tx = None;
+        self.network_serve_tx = None;
+        self.network_restart_tx = None;
+        self.network_ready_tx = None;
+        self.network_chat_tx = None;
+        self.recorder = None;
+        self.game_over = false;
+        self.network_status = NetworkStatus::Idle;
+        self.screen = AppScreen::MainMenu;
+    }
+
     fn exit(&mut self) {
         self.exit = true;
     }
 }
 
 fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+
+    let mut config = config::load().unwrap_or_else(|e| {
+        eprintln!("Ignoring unreadable settings file ({}), using defaults", e);
+        Config::default()
+    });
+    cli.apply(&mut config)?;
+
+    let mut app = App::new(config);
+    if cli.screensaver {
+        app.launch_screensaver();
+    } else if cli.wants_network_game() {
+        app.launch_cli_network_game();
+    }
+
     let terminal = ratatui::init();
-    let mut app = App::new();
 
     let mut stdout = io::stdout();
     stdout.execute(event::EnableMouseCapture)?;
@@ -0,0 +1,529 @@
+use rumqttc::{Client, LastWill, QoS};
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::network::{
+    self, match_queue_topic, match_ticket_of, match_ticket_topic, now_ms, MatchMsg,
+    NetworkConfig, QueueMsg, MATCH_QUEUE_FILTER,
+};
+use crate::server::ServerConfig;
+
+// ---------------------------------------------------------------------------
+// Quick Match
+//
+// Clients queue a ticket on `pong/match/queue/{ticket}` and wait on
+// `pong/match/ticket/{ticket}`. The matchmaker pairs tickets first come, first
+// served and tells both sides a fresh game ID and which slot to take; from
+// there they join like any other online game.
+// ---------------------------------------------------------------------------
+
+/// Queued clients repeat their ticket this often...
+const QUEUE_HEARTBEAT: Duration = Duration::from_secs(5);
+/// ...and are dropped from the queue if they miss three in a row.
+const TICKET_TIMEOUT_MS: u64 = 15_000;
+/// With skill buckets, each this long a ticket has waited lets it pair one bucket further away.
+const BUCKET_WIDEN_MS: u64 = 20_000;
+
+#[derive(Debug, Clone)]
+struct Queued {
+    msg: QueueMsg,
+    /// Matchmaker clock, ms
+    queued_at: u64,
+    seen_at: u64,
+}
+
+/// Two tickets paired into a new game; `players[0]` (the longer waiting) is P1.
+#[derive(Debug, Clone)]
+pub struct Pairing {
+    pub game_id: String,
+    pub players: [QueueMsg; 2],
+}
+
+impl Pairing {
+    /// What each side is told, by ticket
+    pub fn replies(&self, now: u64) -> [(String, MatchMsg); 2] {
+        [0, 1].map(|i| {
+            let reply = MatchMsg {
+                game_id: self.game_id.clone(),
+                player: i as u8 + 1,
+                opponent: self.players[1 - i].name.clone(),
+                timestamp: now,
+            };
+            (self.players[i].ticket.clone(), reply)
+        })
+    }
+}
+
+/// A ticket that has been paired but is still being repeated, most likely
+/// because its client never got the (unretained) reply
+#[derive(Debug, Clone)]
+struct Paired {
+    ticket: String,
+    reply: MatchMsg,
+    seen_at: u64,
+}
+
+/// The queue itself, without any MQTT: feed it tickets and cancels, then `pair`.
+#[derive(Debug, Default)]
+pub struct Matchmaker {
+    /// Skill bucket width; None pairs everyone strictly in arrival order
+    bucket_width: Option<u32>,
+    queue: Vec<Queued>,
+    paired: Vec<Paired>,
+}
+
+impl Matchmaker {
+    pub fn new(bucket_width: Option<u32>) -> Self {
+        Self {
+            bucket_width: bucket_width.filter(|&w| w > 0),
+            queue: Vec::new(),
+            paired: Vec::new(),
+        }
+    }
+
+    /// Add a ticket, or refresh one already queued (it keeps its place). A ticket
+    /// that was already paired isn't queued again; its reply is returned to resend.
+    pub fn enqueue(&mut self, msg: QueueMsg, now: u64) -> Option<MatchMsg> {
+        if let Some(paired) = self.paired.iter_mut().find(|p| p.ticket == msg.ticket) {
+            paired.seen_at = now;
+            return Some(paired.reply.clone());
+        }
+        match self.queue.iter_mut().find(|q| q.msg.ticket == msg.ticket) {
+            Some(queued) => {
+                queued.msg = msg;
+                queued.seen_at = now;
+            }
+            None => self.queue.push(Queued {
+                msg,
+                queued_at: now,
+                seen_at: now,
+            }),
+        }
+        None
+    }
+
+    pub fn cancel(&mut self, ticket: &str) {
+        self.queue.retain(|q| q.msg.ticket != ticket);
+        self.paired.retain(|p| p.ticket != ticket);
+    }
+
+    /// Forget tickets whose client stopped repeating them.
+    pub fn expire(&mut self, now: u64) {
+        self.queue
+            .retain(|q| now.saturating_sub(q.seen_at) < TICKET_TIMEOUT_MS);
+        self.paired
+            .retain(|p| now.saturating_sub(p.seen_at) < TICKET_TIMEOUT_MS);
+    }
+
+    /// Pair off everyone who can be: each ticket, oldest first, with the oldest
+    /// compatible ticket queued after it. Paired tickets are remembered until
+    /// their clients stop repeating them (see `enqueue`).
+    pub fn pair(&mut self, now: u64) -> Vec<Pairing> {
+        let mut pairings = Vec::new();
+        let mut i = 0;
+        while i < self.queue.len() {
+            let partner = (i + 1..self.queue.len())
+                .find(|&j| self.compatible(&self.queue[i], &self.queue[j], now));
+            match partner {
+                Some(j) => {
+                    let second = self.queue.remove(j);
+                    let first = self.queue.remove(i);
+                    let pairing = Pairing {
+                        game_id: format!("qm{:08x}", rand::random::<u32>()),
+                        players: [first.msg, second.msg],
+                    };
+                    let seen = [first.seen_at, second.seen_at];
+                    for ((ticket, reply), seen_at) in pairing.replies(now).into_iter().zip(seen) {
+                        self.paired.push(Paired {
+                            ticket,
+                            reply,
+                            seen_at,
+                        });
+                    }
+                    pairings.push(pairing);
+                }
+                None => i += 1,
+            }
+        }
+        pairings
+    }
+
+    /// Without buckets, or if either side has no rating, anyone goes. Otherwise the
+    /// buckets must be within reach of how long the older ticket has waited.
+    fn compatible(&self, older: &Queued, newer: &Queued, now: u64) -> bool {
+        let (Some(width), Some(a), Some(b)) = (self.bucket_width, older.msg.skill, newer.msg.skill)
+        else {
+            return true;
+        };
+        let reach = now.saturating_sub(older.queued_at) / BUCKET_WIDEN_MS;
+        u64::from((a / width).abs_diff(b / width)) <= reach
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Matchmaker service (blocks forever)
+// ---------------------------------------------------------------------------
+
+enum QueueInput {
+    Queue(QueueMsg),
+    Cancel(String),
+}
+
+fn parse_queue_input(topic: &str, payload: &[u8]) -> Option<QueueInput> {
+    let ticket = match_ticket_of(topic)?;
+    if payload.is_empty() {
+        return Some(QueueInput::Cancel(ticket.to_string()));
+    }
+    serde_json::from_slice::<QueueMsg>(payload)
+        .ok()
+        .filter(|msg| msg.ticket == ticket)
+        .map(QueueInput::Queue)
+}
+
+/// Serve Quick Match on the broker in `config`. `bucket_width` turns on skill
+/// buckets of that many rating points. Returns only if the TLS files can't be loaded.
+pub fn run(config: ServerConfig, bucket_width: Option<u32>) -> io::Result<()> {
+    let client_id = format!("rust-pong-matchmaker-{}", std::process::id());
+    let mut mqttoptions = network::mqtt_options(
+        client_id,
+        &config.broker_host,
+        config.broker_port,
+        config.transport,
+        &config.ws_path,
+        &config.tls,
+    )?;
+    mqttoptions.set_keep_alive(Duration::from_secs(5));
+    if let (Some(user), Some(pass)) = (config.username, config.password) {
+        mqttoptions.set_credentials(user, pass);
+    }
+
+    let (client, mut connection) = Client::new(mqttoptions, 64);
+    let (input_tx, input_rx) = mpsc::channel::<QueueInput>();
+
+    let sub_client = client.clone();
+    thread::spawn(move || {
+        for notification in connection.iter() {
+            match notification {
+                Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                    sub_client.subscribe(MATCH_QUEUE_FILTER, QoS::AtLeastOnce).ok();
+                }
+                Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(msg))) => {
+                    if let Some(input) = parse_queue_input(&msg.topic, &msg.payload) {
+                        if input_tx.send(input).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("pong-matchmaker: connection error: {e}, retrying");
+                    thread::sleep(Duration::from_secs(1));
+                }
+                _ => {}
+            }
+        }
+    });
+
+    let reply = |ticket: &str, msg: &MatchMsg| {
+        if let Ok(payload) = serde_json::to_vec(msg) {
+            client
+                .publish(match_ticket_topic(ticket), QoS::AtLeastOnce, false, payload)
+                .ok();
+        }
+    };
+
+    let mut matchmaker = Matchmaker::new(bucket_width);
+    loop {
+        match input_rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(QueueInput::Queue(msg)) => {
+                let ticket = msg.ticket.clone();
+                // Still queueing after being paired: the reply got lost, send it again
+                if let Some(sent) = matchmaker.enqueue(msg, now_ms()) {
+                    reply(&ticket, &sent);
+                }
+            }
+            Ok(QueueInput::Cancel(ticket)) => matchmaker.cancel(&ticket),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        let now = now_ms();
+        matchmaker.expire(now);
+        for pairing in matchmaker.pair(now) {
+            for (ticket, msg) in pairing.replies(now) {
+                reply(&ticket, &msg);
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Client side: wait in the queue
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub enum MatchEvent {
+    Connected,
+    /// Connection lost; the session keeps retrying
+    Disconnected,
+    Matched(MatchMsg),
+}
+
+/// A ticket in the Quick Match queue. Dropping it leaves the queue.
+pub struct MatchSession {
+    pub rx: mpsc::Receiver<MatchEvent>,
+    client: Client,
+    ticket: String,
+    /// Dropped with the session, which stops the heartbeat thread
+    _heartbeat_stop: mpsc::Sender<()>,
+}
+
+impl Drop for MatchSession {
+    fn drop(&mut self) {
+        self.client
+            .try_publish(match_queue_topic(&self.ticket), QoS::AtLeastOnce, false, Vec::new())
+            .ok();
+        self.client.try_disconnect().ok();
+    }
+}
+
+/// Queue for a match on the broker in `config` (its game and role are ignored),
+/// announcing `config.name` and `skill`. Fails only if the TLS files can't be loaded.
+pub fn queue(config: &NetworkConfig, skill: Option<u32>) -> io::Result<MatchSession> {
+    let ticket = format!("{:08x}", rand::random::<u32>());
+    let mut options = network::mqtt_options(
+        format!("rust-pong-queue-{}", ticket),
+        &config.broker_host,
+        config.broker_port,
+        config.transport,
+        &config.ws_path,
+        &config.tls,
+    )?;
+    options.set_keep_alive(Duration::from_secs(5));
+    if let (Some(user), Some(pass)) = (config.username.clone(), config.password.clone()) {
+        options.set_credentials(user, pass);
+    }
+    let queue_topic = match_queue_topic(&ticket);
+    // Vanishing without a cancel still takes the ticket off the queue
+    options.set_last_will(LastWill::new(&queue_topic, Vec::new(), QoS::AtLeastOnce, false));
+
+    let (client, mut connection) = Client::new(options, 16);
+    let (tx, rx) = mpsc::channel();
+
+    let queue_msg = QueueMsg {
+        ticket: ticket.clone(),
+        name: config.name.clone(),
+        skill,
+        timestamp: 0,
+    };
+    let publish_ticket = {
+        let client = client.clone();
+        let queue_topic = queue_topic.clone();
+        move || {
+            let msg = QueueMsg {
+                timestamp: now_ms(),
+                ..queue_msg.clone()
+            };
+            if let Ok(payload) = serde_json::to_vec(&msg) {
+                client.try_publish(&queue_topic, QoS::AtLeastOnce, false, payload).ok();
+            }
+        }
+    };
+
+    let (heartbeat_stop, heartbeat_stop_rx) = mpsc::channel::<()>();
+    let heartbeat = publish_ticket.clone();
+    thread::spawn(move || {
+        while let Err(mpsc::RecvTimeoutError::Timeout) = heartbeat_stop_rx.recv_timeout(QUEUE_HEARTBEAT) {
+            heartbeat();
+        }
+    });
+
+    let sub_client = client.clone();
+    let ticket_topic = match_ticket_topic(&ticket);
+    thread::spawn(move || {
+        for notification in connection.iter() {
+            let event = match notification {
+                Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                    sub_client.try_subscribe(&ticket_topic, QoS::AtLeastOnce).ok();
+                    publish_ticket();
+                    Some(MatchEvent::Connected)
+                }
+                Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(msg))) => {
+                    if msg.topic == ticket_topic {
+                        serde_json::from_slice::<MatchMsg>(&msg.payload)
+                            .ok()
+                            .map(MatchEvent::Matched)
+                    } else {
+                        None
+                    }
+                }
+                Ok(rumqttc::Event::Outgoing(rumqttc::Outgoing::Disconnect)) => break,
+                Err(_) => {
+                    thread::sleep(Duration::from_secs(1));
+                    Some(MatchEvent::Disconnected)
+                }
+                _ => None,
+            };
+            if let Some(event) = event {
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(MatchSession {
+        rx,
+        client,
+        ticket,
+        _heartbeat_stop: heartbeat_stop,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(name: &str, skill: Option<u32>) -> QueueMsg {
+        QueueMsg {
+            ticket: format!("t-{name}"),
+            name: name.to_string(),
+            skill,
+            timestamp: 0,
+        }
+    }
+
+    fn names(pairing: &Pairing) -> [&str; 2] {
+        [
+            pairing.players[0].name.as_str(),
+            pairing.players[1].name.as_str(),
+        ]
+    }
+
+    #[test]
+    fn pairs_first_come_first_served() {
+        let mut mm = Matchmaker::new(None);
+        mm.enqueue(ticket("a", None), 0);
+        assert!(mm.pair(0).is_empty());
+        mm.enqueue(ticket("b", None), 10);
+        mm.enqueue(ticket("c", None), 20);
+
+        let pairings = mm.pair(20);
+        assert_eq!(pairings.len(), 1);
+        assert_eq!(names(&pairings[0]), ["a", "b"]);
+
+        mm.enqueue(ticket("d", None), 30);
+        assert_eq!(names(&mm.pair(30)[0]), ["c", "d"]);
+    }
+
+    #[test]
+    fn replies_name_the_slot_and_opponent() {
+        let mut mm = Matchmaker::new(None);
+        mm.enqueue(ticket("a", None), 0);
+        mm.enqueue(ticket("b", None), 0);
+        let pairing = mm.pair(0).remove(0);
+        let [(first, to_a), (second, to_b)] = pairing.replies(0);
+        assert_eq!(
+            (first.as_str(), to_a.player, to_a.opponent.as_str()),
+            ("t-a", 1, "b")
+        );
+        assert_eq!(
+            (second.as_str(), to_b.player, to_b.opponent.as_str()),
+            ("t-b", 2, "a")
+        );
+        assert_eq!(to_a.game_id, to_b.game_id);
+    }
+
+    #[test]
+    fn refreshing_a_ticket_keeps_its_place() {
+        let mut mm = Matchmaker::new(None);
+        mm.enqueue(ticket("a", None), 0);
+        mm.enqueue(ticket("b", None), 0);
+        mm.enqueue(ticket("a", None), 5_000);
+        assert_eq!(mm.queue.len(), 2);
+        assert_eq!(names(&mm.pair(5_000)[0]), ["a", "b"]);
+    }
+
+    #[test]
+    fn cancelled_tickets_leave_the_queue() {
+        let mut mm = Matchmaker::new(None);
+        mm.enqueue(ticket("a", None), 0);
+        mm.enqueue(ticket("b", None), 0);
+        mm.cancel("t-a");
+        assert!(mm.pair(0).is_empty());
+        mm.enqueue(ticket("c", None), 0);
+        assert_eq!(names(&mm.pair(0)[0]), ["b", "c"]);
+    }
+
+    #[test]
+    fn tickets_not_repeated_expire() {
+        let mut mm = Matchmaker::new(None);
+        mm.enqueue(ticket("a", None), 0);
+        mm.enqueue(ticket("b", None), 0);
+        mm.enqueue(ticket("b", None), 10_000);
+        mm.expire(TICKET_TIMEOUT_MS);
+        mm.enqueue(ticket("c", None), TICKET_TIMEOUT_MS);
+        assert_eq!(names(&mm.pair(TICKET_TIMEOUT_MS)[0]), ["b", "c"]);
+    }
+
+    #[test]
+    fn same_bucket_pairs_at_once() {
+        let mut mm = Matchmaker::new(Some(100));
+        mm.enqueue(ticket("a", Some(1_210)), 0);
+        mm.enqueue(ticket("b", Some(1_290)), 0);
+        assert_eq!(mm.pair(0).len(), 1);
+    }
+
+    #[test]
+    fn distant_buckets_pair_once_the_wait_widens_them() {
+        let mut mm = Matchmaker::new(Some(100));
+        mm.enqueue(ticket("a", Some(1_000)), 0);
+        mm.enqueue(ticket("b", Some(1_250)), 0);
+        for t in [0, BUCKET_WIDEN_MS, 2 * BUCKET_WIDEN_MS - 1] {
+            mm.enqueue(ticket("a", Some(1_000)), t);
+            mm.enqueue(ticket("b", Some(1_250)), t);
+            assert!(mm.pair(t).is_empty(), "paired at {t}");
+        }
+        assert_eq!(mm.pair(2 * BUCKET_WIDEN_MS).len(), 1);
+    }
+
+    #[test]
+    fn buckets_skip_to_a_compatible_ticket() {
+        let mut mm = Matchmaker::new(Some(100));
+        mm.enqueue(ticket("a", Some(1_000)), 0);
+        mm.enqueue(ticket("far", Some(2_000)), 0);
+        mm.enqueue(ticket("near", Some(1_050)), 0);
+        assert_eq!(names(&mm.pair(0)[0]), ["a", "near"]);
+        assert_eq!(mm.queue.len(), 1);
+    }
+
+    #[test]
+    fn unrated_tickets_pair_with_anyone() {
+        let mut mm = Matchmaker::new(Some(100));
+        mm.enqueue(ticket("a", Some(3_000)), 0);
+        mm.enqueue(ticket("b", None), 0);
+        assert_eq!(mm.pair(0).len(), 1);
+    }
+
+    #[test]
+    fn paired_ticket_gets_the_same_reply_until_it_stops() {
+        let mut mm = Matchmaker::new(None);
+        mm.enqueue(ticket("a", None), 0);
+        mm.enqueue(ticket("b", None), 0);
+        let [(_, to_a), _] = mm.pair(0)[0].replies(0);
+
+        // The reply was lost and the heartbeat re-queues the ticket
+        let resent = mm.enqueue(ticket("a", None), 5_000).unwrap();
+        assert_eq!((resent.game_id, resent.player), (to_a.game_id, 1));
+        assert!(mm.queue.is_empty());
+        mm.enqueue(ticket("c", None), 5_000);
+        assert!(mm.pair(5_000).is_empty());
+
+        // Heartbeats stopped (matched client left the queue) or it cancelled
+        mm.expire(5_000 + TICKET_TIMEOUT_MS);
+        assert!(mm.enqueue(ticket("a", None), 30_000).is_none());
+        mm.cancel("t-a");
+        assert!(mm.paired.is_empty());
+    }
+}
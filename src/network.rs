@@ -1,9 +1,17 @@
-use rumqttc::{Client, MqttOptions, QoS};
+use rumqttc::{Client, LastWill, MqttOptions, QoS, TlsConfiguration};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::sync::mpsc;
+use std::collections::VecDeque;
+use std::io;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
 use std::thread;
 use std::time::Duration;
 
+use crate::sim::SpeedRamp;
+use crate::tls::{self, TlsSettings};
+
 // ---------------------------------------------------------------------------
 // Message types (shared between client and server)
 // ---------------------------------------------------------------------------
@@ -34,7 +42,18 @@ pub struct StateMsg {
     pub p1_score: u32,
     #[serde(rename = "p2Score")]
     pub p2_score: u32,
+    /// Profile names of whoever holds each slot, when they sent one
+    #[serde(rename = "p1Name", default, skip_serializing_if = "Option::is_none")]
+    pub p1_name: Option<String>,
+    #[serde(rename = "p2Name", default, skip_serializing_if = "Option::is_none")]
+    pub p2_name: Option<String>,
     pub status: GameStatus,
+    /// Paddle returns since the last serve
+    #[serde(default)]
+    pub rally: u32,
+    /// The game's speed ramp (older servers don't send one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ramp: Option<SpeedRamp>,
     pub timestamp: u64,
 }
 
@@ -46,10 +65,133 @@ pub enum GameStatus {
     Ended,
 }
 
-/// Join notification sent by client → server on connect
+/// Join notification sent by client → server on connect. With `client` set it
+/// is also a claim on the slot, answered with a `ClaimMsg`.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JoinMsg {
     pub player: u8, // 1 or 2
+    /// Random per-session tag; older clients leave it out and aren't checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    /// Profile name, passed on to everyone in the game's `StateMsg`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Speed ramp to use if this join opens the game
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ramp: Option<SpeedRamp>,
+    /// Which of the session's connections this is (0, then +1 per reconnect)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<u32>,
+    pub timestamp: u64,
+}
+
+/// Sent by server → the claiming client on `pong/game/{id}/claim/{client}`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClaimMsg {
+    pub player: u8,
+    pub granted: bool,
+    /// When refused: the other slot, if nobody holds it
+    pub free: Option<u8>,
+    pub timestamp: u64,
+}
+
+/// Sent by client → server when it leaves a game, and left with the broker as
+/// its last will so a vanished client's slot is freed too
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LeaveMsg {
+    pub client: String,
+    /// Connection it was sent from (see `JoinMsg`); a late last will from an
+    /// older connection than the owner's latest join is ignored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection: Option<u32>,
+    pub timestamp: u64,
+}
+
+/// Sent by client → server: "launch the ball" (ignored while a rally is live)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ServeMsg {
+    pub player: u8,
+    pub timestamp: u64,
+}
+
+/// Sent by client → server: reset an ended game (ignored unless status is Ended)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RestartMsg {
+    pub timestamp: u64,
+}
+
+/// Sent by client → server after game over: this player wants a rematch
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReadyMsg {
+    pub player: u8,
+    pub timestamp: u64,
+}
+
+/// Sent by client → server once a second to measure round trip and clock offset
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PingMsg {
+    pub seq: u32,
+    pub timestamp: u64,
+}
+
+/// Sent by server → the pinging client, echoing the ping. `received` and
+/// `timestamp` are the server's clock when the ping arrived and the reply left.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PongMsg {
+    pub seq: u32,
+    pub ping_timestamp: u64,
+    pub received: u64,
+    pub timestamp: u64,
+}
+
+/// Sent by any client (spectators included) → everyone in the game
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatMsg {
+    /// Sender's session tag, for per-sender rate limiting
+    pub client: String,
+    pub name: String,
+    /// Sender's slot; None for spectators
+    pub player: Option<u8>,
+    pub text: String,
+    pub timestamp: u64,
+}
+
+/// Retained on `pong/lobby/{game_id}/p{n}` by each player while they are in a game,
+/// so the lobby browser can list games. Cleared with an empty retained payload when
+/// the player leaves, or by the server when their last will (`LeaveMsg`) arrives.
+/// Re-published every `LOBBY_REFRESH_MS`, so announcements that nobody cleared
+/// (no server on the broker, or it missed the will) age out of the browser.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LobbyMsg {
+    pub game_id: String,
+    pub player: u8,
+    pub name: String,
+    pub status: GameStatus,
+    /// When this player joined the game
+    pub since: u64,
+    /// When this announcement was (re)published
+    pub timestamp: u64,
+}
+
+/// Sent by a Quick Match client → matchmaker on `pong/match/queue/{ticket}`, and
+/// repeated every few seconds while it waits. An empty payload on the same topic
+/// (sent on cancel, or by the broker as the last will) takes the ticket off the queue.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueueMsg {
+    pub ticket: String,
+    pub name: String,
+    /// Optional rating; only used by matchmakers running with skill buckets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill: Option<u32>,
+    pub timestamp: u64,
+}
+
+/// Sent by matchmaker → each paired client on `pong/match/ticket/{ticket}`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatchMsg {
+    pub game_id: String,
+    pub player: u8,
+    pub opponent: String,
     pub timestamp: u64,
 }
 
@@ -57,6 +199,26 @@ pub struct JoinMsg {
 // Topic helpers
 // ---------------------------------------------------------------------------
 
+/// Every lobby announcement, for the lobby browser
+pub const LOBBY_FILTER: &str = "pong/lobby/+/+";
+/// Every queued Quick Match ticket, for the matchmaker
+pub const MATCH_QUEUE_FILTER: &str = "pong/match/queue/+";
+
+pub fn match_queue_topic(ticket: &str) -> String {
+    format!("pong/match/queue/{}", ticket)
+}
+
+pub fn match_ticket_topic(ticket: &str) -> String {
+    format!("pong/match/ticket/{}", ticket)
+}
+
+/// Ticket of a queue topic, e.g. "pong/match/queue/9f3a" → "9f3a"
+pub fn match_ticket_of(topic: &str) -> Option<&str> {
+    topic
+        .strip_prefix("pong/match/queue/")
+        .filter(|ticket| !ticket.is_empty() && !ticket.contains('/'))
+}
+
 pub struct Topics {
     pub game_id: String,
 }
@@ -88,6 +250,19 @@ impl Topics {
         format!("pong/game/{}/join", self.game_id)
     }
 
+    /// Replies to one client's slot claims
+    pub fn claim(&self, client: &str) -> String {
+        format!("pong/game/{}/claim/{}", self.game_id, client)
+    }
+
+    pub fn leave(&self) -> String {
+        format!("pong/game/{}/leave", self.game_id)
+    }
+
+    pub fn chat(&self) -> String {
+        format!("pong/game/{}/chat", self.game_id)
+    }
+
     pub fn serve(&self) -> String {
         format!("pong/game/{}/serve", self.game_id)
     }
@@ -99,6 +274,39 @@ impl Topics {
     pub fn ready(&self) -> String {
         format!("pong/game/{}/ready", self.game_id)
     }
+
+    /// Per client (`Role::topic_name`) so each one only hears replies to its own pings
+    pub fn ping(&self, who: &str) -> String {
+        format!("pong/game/{}/{}/ping", self.game_id, who)
+    }
+
+    pub fn pong(&self, who: &str) -> String {
+        format!("pong/game/{}/{}/pong", self.game_id, who)
+    }
+
+    pub fn lobby(&self, player: u8) -> String {
+        format!("pong/lobby/{}/p{}", self.game_id, player)
+    }
+
+    /// Game ID and player slot of a lobby announcement topic, e.g. "pong/lobby/abc/p2" → ("abc", 2)
+    pub fn lobby_slot_of(topic: &str) -> Option<(&str, u8)> {
+        let (game_id, slot) = topic.strip_prefix("pong/lobby/")?.split_once('/')?;
+        let player = match slot {
+            "p1" => 1,
+            "p2" => 2,
+            _ => return None,
+        };
+        Some((game_id, player)).filter(|(id, _)| !id.is_empty())
+    }
+
+    /// Extract the game ID from a concrete topic, e.g. "pong/game/abc/ball" → "abc".
+    /// Used by the server, which subscribes with `Topics::new("+")` wildcards.
+    pub fn game_id_of(topic: &str) -> Option<&str> {
+        topic
+            .strip_prefix("pong/game/")
+            .and_then(|rest| rest.split('/').next())
+            .filter(|id| !id.is_empty())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -107,29 +315,223 @@ impl Topics {
 
 #[derive(Debug, Clone)]
 pub enum NetworkEvent {
-    /// Opponent paddle moved to this Y (physics/court units)
-    OpponentPaddle(f32),
+    /// A remote paddle moved: player number (1 or 2) and its position (physics/court
+    /// units, stamped by that player's clock). Players only hear their opponent.
+    Paddle(u8, PaddleMsg),
     /// Server published authoritative ball state
     BallUpdate(BallMsg),
     /// Server published scores / status
     StateUpdate(StateMsg),
     /// MQTT connection established
     Connected,
-    /// MQTT connection lost
+    /// MQTT connection lost; retrying. Carries the attempt number, starting at 1.
+    Reconnecting(u32),
+    /// MQTT connection lost for good (reconnect attempts exhausted)
     Disconnected,
+    /// New round-trip / clock offset estimate, after each answered ping
+    ClockSync(ClockSync),
+    /// Someone in the game (possibly us) said something
+    Chat(ChatMsg),
+    /// The server refused our slot because another client holds it. Carries the
+    /// requested slot and the other one if it is free. The session has ended.
+    SlotTaken { requested: u8, free: Option<u8> },
+}
+
+// ---------------------------------------------------------------------------
+// Clock synchronization (NTP-style, over the ping/pong topics)
+//
+//   t0 = ping sent (client clock)     t1 = ping received (server clock)
+//   t3 = pong received (client clock) t2 = pong sent (server clock)
+//
+//   rtt    = (t3 - t0) - (t2 - t1)
+//   offset = ((t1 - t0) + (t2 - t3)) / 2     server clock minus ours
+//
+// The offset is exact when both legs take equally long, so it is taken from
+// the lowest-RTT sample of the last few: queueing delay is rarely symmetric.
+// ---------------------------------------------------------------------------
+
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+/// Replies slower than this are dropped (e.g. pings queued across a reconnect)
+const PING_TIMEOUT_MS: u64 = 5_000;
+const CLOCK_SAMPLES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClockSync {
+    /// Smoothed round-trip time, ms
+    pub rtt_ms: f64,
+    /// Mean variation between consecutive round trips, ms (RFC 3550 style)
+    pub jitter_ms: f64,
+    /// Server clock minus local clock, ms
+    pub offset_ms: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClockEstimator {
+    /// (rtt, offset) of the most recent replies, oldest first
+    samples: VecDeque<(f64, f64)>,
+    srtt: Option<f64>,
+    last_rtt: Option<f64>,
+    jitter: f64,
+}
+
+impl ClockEstimator {
+    /// Feed a pong received at `local_ms`; returns the updated estimate.
+    pub fn on_pong(&mut self, msg: &PongMsg, local_ms: u64) -> Option<ClockSync> {
+        let elapsed = local_ms.checked_sub(msg.ping_timestamp)?;
+        if elapsed > PING_TIMEOUT_MS {
+            return None;
+        }
+        let server_hold = msg.timestamp.saturating_sub(msg.received);
+        let rtt = elapsed.saturating_sub(server_hold) as f64;
+        let offset = ((msg.received as f64 - msg.ping_timestamp as f64)
+            + (msg.timestamp as f64 - local_ms as f64))
+            / 2.0;
+
+        self.samples.push_back((rtt, offset));
+        while self.samples.len() > CLOCK_SAMPLES {
+            self.samples.pop_front();
+        }
+        let srtt = match self.srtt {
+            Some(srtt) => srtt + (rtt - srtt) / 8.0,
+            None => rtt,
+        };
+        self.srtt = Some(srtt);
+        if let Some(last) = self.last_rtt {
+            self.jitter += ((rtt - last).abs() - self.jitter) / 16.0;
+        }
+        self.last_rtt = Some(rtt);
+
+        let (_, offset) = self
+            .samples
+            .iter()
+            .copied()
+            .min_by(|a, b| a.0.total_cmp(&b.0))?;
+        Some(ClockSync {
+            rtt_ms: srtt,
+            jitter_ms: self.jitter,
+            offset_ms: offset,
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Traffic counters (read by the diagnostics overlay)
+// ---------------------------------------------------------------------------
+
+/// Running message counts, bumped by the network threads as packets come and go
+#[derive(Debug, Default)]
+pub struct TrafficCounters {
+    pub(crate) paddle_in: AtomicU64,
+    pub(crate) ball_in: AtomicU64,
+    pub(crate) state_in: AtomicU64,
+    pub(crate) pong_in: AtomicU64,
+    pub(crate) chat_in: AtomicU64,
+    /// Payloads on our topics that weren't valid JSON for the topic's message type
+    pub(crate) unparsable: AtomicU64,
+    pub(crate) paddle_out: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrafficSnapshot {
+    pub paddle_in: u64,
+    pub ball_in: u64,
+    pub state_in: u64,
+    pub pong_in: u64,
+    pub chat_in: u64,
+    pub unparsable: u64,
+    pub paddle_out: u64,
+}
+
+impl TrafficCounters {
+    pub fn snapshot(&self) -> TrafficSnapshot {
+        TrafficSnapshot {
+            paddle_in: self.paddle_in.load(Ordering::Relaxed),
+            ball_in: self.ball_in.load(Ordering::Relaxed),
+            state_in: self.state_in.load(Ordering::Relaxed),
+            pong_in: self.pong_in.load(Ordering::Relaxed),
+            chat_in: self.chat_in.load(Ordering::Relaxed),
+            unparsable: self.unparsable.load(Ordering::Relaxed),
+            paddle_out: self.paddle_out.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Count a packet on `counter`'s topic and parse it
+    fn receive<T: DeserializeOwned>(&self, counter: &AtomicU64, payload: &[u8]) -> Option<T> {
+        counter.fetch_add(1, Ordering::Relaxed);
+        let parsed = serde_json::from_slice(payload).ok();
+        if parsed.is_none() {
+            self.unparsable.fetch_add(1, Ordering::Relaxed);
+        }
+        parsed
+    }
+}
+
+impl TrafficSnapshot {
+    /// Counts accumulated since `earlier`
+    pub fn since(&self, earlier: &TrafficSnapshot) -> TrafficSnapshot {
+        TrafficSnapshot {
+            paddle_in: self.paddle_in - earlier.paddle_in,
+            ball_in: self.ball_in - earlier.ball_in,
+            state_in: self.state_in - earlier.state_in,
+            pong_in: self.pong_in - earlier.pong_in,
+            chat_in: self.chat_in - earlier.chat_in,
+            unparsable: self.unparsable - earlier.unparsable,
+            paddle_out: self.paddle_out - earlier.paddle_out,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Config
 // ---------------------------------------------------------------------------
 
+/// Who this client is in a game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Role {
+    /// Plays slot 1 or 2
+    Player(u8),
+    /// Watches both paddles; never publishes paddle, serve, ready or join messages.
+    /// Carries a random tag that keeps spectators' client IDs and ping topics apart.
+    Spectator(String),
+}
+
+impl Role {
+    pub fn spectator() -> Self {
+        Role::Spectator(format!("{:08x}", rand::random::<u32>()))
+    }
+
+    /// Slot number for players, None for spectators
+    pub fn player(&self) -> Option<u8> {
+        match self {
+            Role::Player(n) => Some(*n),
+            Role::Spectator(_) => None,
+        }
+    }
+
+    /// Topic segment for this client's own ping/pong topics
+    fn topic_name(&self) -> String {
+        match self {
+            Role::Player(n) => format!("p{}", n),
+            Role::Spectator(tag) => format!("spectator-{}", tag),
+        }
+    }
+}
+
 pub struct NetworkConfig {
     pub broker_host: String,
     pub broker_port: u16,
+    pub transport: Transport,
+    /// HTTP path of the broker's WebSocket endpoint (WebSocket transport only)
+    pub ws_path: String,
     pub game_id: String,
-    pub player: u8, // 1 or 2
+    pub role: Role,
+    /// Profile name, shown to the opponent and in the lobby browser
+    pub name: String,
+    /// Speed ramp asked for when this client opens the game
+    pub ramp: SpeedRamp,
     pub username: Option<String>,
     pub password: Option<String>,
+    pub tls: TlsSettings,
 }
 
 impl Default for NetworkConfig {
@@ -137,14 +539,162 @@ impl Default for NetworkConfig {
         Self {
             broker_host: "3.141.116.27".to_string(),
             broker_port: 1883,
+            transport: Transport::Tcp,
+            ws_path: DEFAULT_WS_PATH.to_string(),
             game_id: "demo".to_string(),
-            player: 1,
+            role: Role::Player(1),
+            name: "Player".to_string(),
+            ramp: SpeedRamp::default(),
             username: Some("raptor".to_string()),
             password: Some("raptorMQTT2025".to_string()),
+            tls: TlsSettings::default(),
+        }
+    }
+}
+
+pub const DEFAULT_WS_PATH: &str = "/mqtt";
+
+/// How MQTT packets reach the broker. WebSocket runs over HTTP(S), so it gets
+/// through networks and proxies that only allow ports 80/443.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    #[default]
+    Tcp,
+    WebSocket,
+}
+
+impl Transport {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "tcp" | "mqtt" => Some(Transport::Tcp),
+            "websocket" | "ws" => Some(Transport::WebSocket),
+            _ => None,
         }
     }
 }
 
+/// A broker as typed in the lobby: `host[:port]` for plain MQTT, or a URL
+/// (`mqtt://`, `mqtts://`, `ws://`, `wss://`) to pick the transport and TLS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokerEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub transport: Transport,
+    pub ws_path: String,
+    pub tls: bool,
+}
+
+impl BrokerEndpoint {
+    /// A bare `host[:port]` falls back to `default_port`; a URL without a port
+    /// gets its scheme's standard one (1883, 8883, 80, 443) and a WebSocket
+    /// URL without a path gets `/mqtt`. An unknown scheme, a bad port or a
+    /// missing host is an error rather than a guess.
+    pub fn parse(address: &str, default_port: u16) -> Result<Self, String> {
+        let address = address.trim();
+        let (scheme, rest) = match address.split_once("://") {
+            Some((scheme, rest)) => (Some(scheme.to_ascii_lowercase()), rest),
+            None => (None, address),
+        };
+        let (transport, tls, scheme_port) = match scheme.as_deref() {
+            Some("mqtt" | "tcp") => (Transport::Tcp, false, 1883),
+            Some("mqtts" | "ssl" | "tls") => (Transport::Tcp, true, 8883),
+            Some("ws") => (Transport::WebSocket, false, 80),
+            Some("wss") => (Transport::WebSocket, true, 443),
+            Some(other) => {
+                return Err(format!("unknown scheme '{}' (mqtt, mqtts, ws, wss)", other));
+            }
+            None => (Transport::Tcp, false, default_port),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, "")) => (host, scheme_port),
+            Some((host, port)) => match port.parse() {
+                Ok(port) => (host, port),
+                Err(_) => return Err(format!("invalid port '{}'", port)),
+            },
+            None => (authority, scheme_port),
+        };
+        if host.is_empty() {
+            return Err("missing host".to_string());
+        }
+        let ws_path = if path.is_empty() { DEFAULT_WS_PATH } else { path };
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            transport,
+            ws_path: ws_path.to_string(),
+            tls,
+        })
+    }
+}
+
+/// Round-trips through `parse`: plain TCP prints as `host:port`, anything else as a URL.
+impl std::fmt::Display for BrokerEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.transport, self.tls) {
+            (Transport::Tcp, false) => write!(f, "{}:{}", self.host, self.port),
+            (Transport::Tcp, true) => write!(f, "mqtts://{}:{}", self.host, self.port),
+            (Transport::WebSocket, tls) => write!(
+                f,
+                "{}://{}:{}{}",
+                if tls { "wss" } else { "ws" },
+                self.host,
+                self.port,
+                self.ws_path
+            ),
+        }
+    }
+}
+
+/// MQTT options for the given broker, transport and TLS settings. Shared by
+/// the client and the server; fails only if the TLS files can't be loaded.
+pub fn mqtt_options(
+    client_id: String,
+    host: &str,
+    port: u16,
+    transport: Transport,
+    ws_path: &str,
+    tls: &TlsSettings,
+) -> io::Result<MqttOptions> {
+    let tls_config = if tls.enabled {
+        Some(TlsConfiguration::Rustls(Arc::new(tls::client_config(tls)?)))
+    } else {
+        None
+    };
+
+    let mut options = match transport {
+        Transport::Tcp => MqttOptions::new(client_id, host, port),
+        Transport::WebSocket => {
+            let scheme = if tls.enabled { "wss" } else { "ws" };
+            let path = if ws_path.starts_with('/') {
+                ws_path.to_string()
+            } else {
+                format!("/{}", ws_path)
+            };
+            // rumqttc takes host and port from the URL for WebSocket transports
+            MqttOptions::new(client_id, format!("{}://{}:{}{}", scheme, host, port, path), port)
+        }
+    };
+
+    match (transport, tls_config) {
+        (Transport::Tcp, None) => {}
+        (Transport::Tcp, Some(config)) => {
+            options.set_transport(rumqttc::Transport::tls_with_config(config));
+        }
+        (Transport::WebSocket, None) => {
+            options.set_transport(rumqttc::Transport::Ws);
+        }
+        (Transport::WebSocket, Some(config)) => {
+            options.set_transport(rumqttc::Transport::wss_with_config(config));
+        }
+    }
+    Ok(options)
+}
+
 // ---------------------------------------------------------------------------
 // Network handle - returned to the game loop
 // ---------------------------------------------------------------------------
@@ -160,173 +710,657 @@ pub struct NetworkHandle {
     pub restart_tx: mpsc::SyncSender<()>,
     /// Game loop sends () here to publish a ready message (post-game restart coordination)
     pub ready_tx: mpsc::SyncSender<()>,
+    /// Game loop sends chat lines here; spectators can chat too
+    pub chat_tx: mpsc::SyncSender<String>,
+    /// Message counts for the diagnostics overlay
+    pub traffic: Arc<TrafficCounters>,
 }
 
 // ---------------------------------------------------------------------------
 // Spawn the MQTT thread
 // ---------------------------------------------------------------------------
 
-pub fn connect(config: NetworkConfig) -> NetworkHandle {
+/// Give up (and send `NetworkEvent::Disconnected`) after this many failed attempts in a row
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(16);
+
+/// Servers that predate slot claims never answer one; after this long the claim
+/// is taken as granted
+const CLAIM_TIMEOUT_MS: u64 = 3_000;
+
+/// Players re-announce themselves in the lobby this often while in a game
+pub const LOBBY_REFRESH_MS: u64 = 30_000;
+
+/// Exponential backoff: 0.5s, 1s, 2s, ... capped at 16s. None once the
+/// attempts run out.
+fn reconnect_delay(attempt: u32) -> Option<Duration> {
+    (attempt <= MAX_RECONNECT_ATTEMPTS).then(|| {
+        RECONNECT_BASE_DELAY
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(RECONNECT_MAX_DELAY)
+    })
+}
+
+/// `LeaveMsg` for connection number `connection` of `session`, sent on leaving
+/// and set as the last will of that connection
+fn leave_payload(session: &str, connection: u32) -> Vec<u8> {
+    serde_json::to_vec(&LeaveMsg {
+        client: session.to_string(),
+        connection: Some(connection),
+        timestamp: now_ms(),
+    })
+    .unwrap_or_default()
+}
+
+/// Fails only if the TLS files can't be loaded; connection problems arrive
+/// later as `NetworkEvent::Disconnected`.
+pub fn connect(config: NetworkConfig) -> io::Result<NetworkHandle> {
     let (event_tx, event_rx) = mpsc::channel::<NetworkEvent>();
     let (paddle_tx, paddle_rx) = mpsc::sync_channel::<f32>(32);
     let (serve_tx, serve_rx) = mpsc::sync_channel::<()>(4);
     let (restart_tx, restart_rx) = mpsc::sync_channel::<()>(4);
     let (ready_tx, ready_rx) = mpsc::sync_channel::<()>(4);
+    let (chat_tx, chat_rx) = mpsc::sync_channel::<String>(8);
 
-    thread::spawn(move || {
-        let topics = Topics::new(&config.game_id);
-        let client_id = format!("rust-pong-p{}-{}", config.player, &config.game_id[..4.min(config.game_id.len())]);
+    let traffic = Arc::new(TrafficCounters::default());
+    let thread_traffic = traffic.clone();
 
-        let mut mqttoptions = MqttOptions::new(client_id, &config.broker_host, config.broker_port);
-        mqttoptions.set_keep_alive(Duration::from_secs(5));
+    // Tells our slot claims apart from another client asking for the same slot
+    let session = format!("{:08x}", rand::random::<u32>());
+    let client_id = format!("rust-pong-{}-{}", config.role.topic_name(), session);
 
-        if let (Some(user), Some(pass)) = (config.username, config.password) {
-            mqttoptions.set_credentials(user, pass);
-        }
+    let mut mqttoptions = mqtt_options(
+        client_id,
+        &config.broker_host,
+        config.broker_port,
+        config.transport,
+        &config.ws_path,
+        &config.tls,
+    )?;
+    mqttoptions.set_keep_alive(Duration::from_secs(5));
+
+    if let (Some(user), Some(pass)) = (config.username.clone(), config.password.clone()) {
+        mqttoptions.set_credentials(user, pass);
+    }
+
+    // Players announce themselves in the lobby once their slot is granted. If they
+    // vanish, the broker tells the server, which frees the slot and the announcement.
+    let lobby_topic = config
+        .role
+        .player()
+        .map(|player| Topics::new(&config.game_id).lobby(player));
+    // Re-stamped before every reconnect: a dead connection's will can reach the
+    // server after our next join, and must not free the slot we just reclaimed
+    let is_player = config.role.player().is_some();
+    if is_player {
+        let topic = Topics::new(&config.game_id).leave();
+        mqttoptions.set_last_will(LastWill::new(topic, leave_payload(&session, 0), QoS::AtLeastOnce, false));
+    }
+
+    thread::spawn(move || {
+        let traffic = thread_traffic;
+        let topics = Topics::new(&config.game_id);
 
         let (client, mut connection) = Client::new(mqttoptions, 64);
 
-        // Subscribe to the topics we care about
-        let my_paddle = if config.player == 1 {
-            topics.p1_paddle()
-        } else {
-            topics.p2_paddle()
+        // Remote paddles we subscribe to: the opponent's, or both when spectating
+        let remote_paddles: Vec<(u8, String)> = match config.role.player() {
+            Some(1) => vec![(2, topics.p2_paddle())],
+            Some(_) => vec![(1, topics.p1_paddle())],
+            None => vec![(1, topics.p1_paddle()), (2, topics.p2_paddle())],
         };
-        let opponent_paddle = if config.player == 1 {
-            topics.p2_paddle()
+
+        // Players start publishing once the server grants their slot. Spectators
+        // publish nothing but pings: drop the queues so the senders fail fast.
+        let mut queues = if config.role.player().is_some() {
+            Some(PublishQueues {
+                paddle_rx,
+                serve_rx,
+                restart_rx,
+                ready_rx,
+            })
         } else {
-            topics.p1_paddle()
+            drop((paddle_rx, serve_rx, restart_rx, ready_rx));
+            None
         };
+        let claim_topic = topics.claim(&session);
 
-        // We subscribe to: opponent paddle, ball, state
-        client.subscribe(&opponent_paddle, QoS::AtMostOnce).ok();
-        client.subscribe(topics.ball(), QoS::AtMostOnce).ok();
-        client.subscribe(topics.state(), QoS::AtMostOnce).ok();
-
-        // Announce join
-        let join_payload = serde_json::to_vec(&JoinMsg {
-            player: config.player,
-            timestamp: now_ms(),
-        })
-        .unwrap_or_default();
-        client.publish(topics.join(), QoS::AtMostOnce, false, join_payload).ok();
-
-        // Send a restart request immediately after join.
-        // If the game is in 'ended' state (stale session on server), this resets it.
-        // If the game is 'waiting' or 'playing', the server ignores it.
-        #[derive(serde::Serialize)]
-        struct RestartMsg { timestamp: u64 }
-        if let Ok(payload) = serde_json::to_vec(&RestartMsg { timestamp: now_ms() }) {
-            client.publish(topics.restart(), QoS::AtMostOnce, false, payload).ok();
-        }
-
-        // Spawn a sub-thread to forward outgoing paddle positions (physics units)
-        let publish_client = client.clone();
-        let my_paddle_topic = my_paddle.clone();
+        // Spawn a sub-thread to forward chat lines
+        let chat_client = client.clone();
+        let chat_topic = topics.chat();
+        let chat_session = session.clone();
+        let chat_name = config.name.clone();
+        let chat_player = config.role.player();
         thread::spawn(move || {
-            while let Ok(y) = paddle_rx.recv() {
-                let msg = PaddleMsg {
-                    y,
+            while let Ok(text) = chat_rx.recv() {
+                let msg = ChatMsg {
+                    client: chat_session.clone(),
+                    name: chat_name.clone(),
+                    player: chat_player,
+                    text,
                     timestamp: now_ms(),
                 };
                 if let Ok(payload) = serde_json::to_vec(&msg) {
-                    publish_client
-                        .publish(&my_paddle_topic, QoS::AtMostOnce, true, payload)
-                        .ok();
+                    chat_client.publish(&chat_topic, QoS::AtLeastOnce, false, payload).ok();
                 }
             }
         });
 
-        // Spawn a sub-thread to forward serve signals
-        let serve_client = client.clone();
-        let serve_topic = topics.serve();
-        let player_num = config.player;
+        // Spawn a sub-thread to ping the server while connected. It stops when
+        // this thread drops `ping_stop`, i.e. when the session ends.
+        let online = Arc::new(AtomicBool::new(false));
+        let (ping_stop, ping_stop_rx) = mpsc::channel::<()>();
+        let ping_client = client.clone();
+        let ping_topic = topics.ping(&config.role.topic_name());
+        let ping_online = online.clone();
         thread::spawn(move || {
-            while let Ok(()) = serve_rx.recv() {
-                #[derive(serde::Serialize)]
-                struct ServeMsg { player: u8, timestamp: u64 }
-                if let Ok(payload) = serde_json::to_vec(&ServeMsg { player: player_num, timestamp: now_ms() }) {
-                    serve_client.publish(&serve_topic, QoS::AtMostOnce, false, payload).ok();
+            let mut seq: u32 = 0;
+            while let Err(mpsc::RecvTimeoutError::Timeout) = ping_stop_rx.recv_timeout(PING_INTERVAL) {
+                if !ping_online.load(Ordering::Relaxed) {
+                    continue;
                 }
-            }
-        });
-
-        // Spawn a sub-thread to forward restart signals
-        let restart_client = client.clone();
-        let restart_topic = topics.restart();
-        thread::spawn(move || {
-            while let Ok(()) = restart_rx.recv() {
-                #[derive(serde::Serialize)]
-                struct RestartMsg2 { timestamp: u64 }
-                if let Ok(payload) = serde_json::to_vec(&RestartMsg2 { timestamp: now_ms() }) {
-                    restart_client.publish(&restart_topic, QoS::AtMostOnce, false, payload).ok();
+                seq = seq.wrapping_add(1);
+                if let Ok(payload) = serde_json::to_vec(&PingMsg { seq, timestamp: now_ms() }) {
+                    ping_client.try_publish(&ping_topic, QoS::AtMostOnce, false, payload).ok();
                 }
             }
         });
+        let pong_topic = topics.pong(&config.role.topic_name());
+        let mut clock = ClockEstimator::default();
 
-        // Spawn a sub-thread to forward ready signals (post-game restart coordination)
-        let ready_client = client.clone();
-        let ready_topic = topics.ready();
-        let player_num2 = config.player;
-        thread::spawn(move || {
-            while let Ok(()) = ready_rx.recv() {
-                #[derive(serde::Serialize)]
-                struct ReadyMsg { player: u8, timestamp: u64 }
-                if let Ok(payload) = serde_json::to_vec(&ReadyMsg { player: player_num2, timestamp: now_ms() }) {
-                    ready_client.publish(&ready_topic, QoS::AtMostOnce, false, payload).ok();
-                }
+        let since = now_ms();
+        let mut lobby_status = GameStatus::Waiting;
+        let announced_at = Cell::new(0);
+        let announce = |status: &GameStatus| {
+            let (Some(topic), Some(player)) = (&lobby_topic, config.role.player()) else {
+                return;
+            };
+            announced_at.set(now_ms());
+            let msg = LobbyMsg {
+                game_id: config.game_id.clone(),
+                player,
+                name: config.name.clone(),
+                status: status.clone(),
+                since,
+                timestamp: now_ms(),
+            };
+            if let Ok(payload) = serde_json::to_vec(&msg) {
+                client.try_publish(topic, QoS::AtLeastOnce, true, payload).ok();
             }
-        });
+        };
 
-        // Main event loop for incoming MQTT messages
-        for notification in connection.iter() {
+        // Main event loop for incoming MQTT messages. Iterating again after an
+        // error makes rumqttc reconnect, so errors back off instead of ending the session.
+        let mut attempt = 0;
+        let mut left = false;
+        let mut claimed = false;
+        // Local time our latest claim went out, until it is answered
+        let mut claim_sent: Option<u64> = None;
+        let mut connection_no = 0;
+        while let Ok(notification) = connection.recv() {
+            let mut granted = false;
             match notification {
                 Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
-                    event_tx.send(NetworkEvent::Connected).ok();
+                    attempt = 0;
+                    online.store(true, Ordering::Relaxed);
+
+                    // Clean sessions forget subscriptions, so (re)subscribe on every ConnAck:
+                    // remote paddles, ball, state. try_* because this thread drives the
+                    // event loop and must never block on a full request queue.
+                    for (_, topic) in &remote_paddles {
+                        client.try_subscribe(topic, QoS::AtMostOnce).ok();
+                    }
+                    client.try_subscribe(topics.ball(), QoS::AtMostOnce).ok();
+                    client.try_subscribe(topics.state(), QoS::AtMostOnce).ok();
+                    client.try_subscribe(&pong_topic, QoS::AtMostOnce).ok();
+                    client.try_subscribe(topics.chat(), QoS::AtLeastOnce).ok();
+
+                    if let Some(player) = config.role.player() {
+                        // (Re)claim our slot; the server remembers us, so a reconnect keeps it
+                        client.try_subscribe(&claim_topic, QoS::AtLeastOnce).ok();
+                        if let Ok(payload) = serde_json::to_vec(&JoinMsg {
+                            player,
+                            client: Some(session.clone()),
+                            name: Some(config.name.clone()),
+                            ramp: Some(config.ramp),
+                            connection: Some(connection_no),
+                            timestamp: now_ms(),
+                        }) {
+                            client.try_publish(topics.join(), QoS::AtLeastOnce, false, payload).ok();
+                            claim_sent = Some(now_ms());
+                        }
+                    }
+                    if claimed {
+                        announce(&lobby_status);
+                    }
+
+                    if event_tx.send(NetworkEvent::Connected).is_err() {
+                        left = true; // game left; drop the session
+                        break;
+                    }
                 }
                 Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(msg))) => {
                     let t = &msg.topic;
 
-                    if *t == opponent_paddle {
-                        if let Ok(p) = serde_json::from_slice::<PaddleMsg>(&msg.payload) {
-                            event_tx.send(NetworkEvent::OpponentPaddle(p.y as f32)).ok();
-                        }
+                    let paddle_of = remote_paddles.iter().find(|(_, topic)| topic == t);
+
+                    let event = if let Some(&(player, _)) = paddle_of {
+                        traffic
+                            .receive::<PaddleMsg>(&traffic.paddle_in, &msg.payload)
+                            .map(|p| NetworkEvent::Paddle(player, p))
                     } else if *t == topics.ball() {
-                        if let Ok(b) = serde_json::from_slice::<BallMsg>(&msg.payload) {
-                            event_tx.send(NetworkEvent::BallUpdate(b)).ok();
-                        }
+                        traffic
+                            .receive::<BallMsg>(&traffic.ball_in, &msg.payload)
+                            .map(NetworkEvent::BallUpdate)
                     } else if *t == topics.state() {
-                        if let Ok(s) = serde_json::from_slice::<StateMsg>(&msg.payload) {
-                            event_tx.send(NetworkEvent::StateUpdate(s)).ok();
+                        traffic
+                            .receive::<StateMsg>(&traffic.state_in, &msg.payload)
+                            .inspect(|s| {
+                                if s.status != lobby_status {
+                                    lobby_status = s.status.clone();
+                                    if claimed {
+                                        announce(&lobby_status);
+                                    }
+                                }
+                            })
+                            .map(NetworkEvent::StateUpdate)
+                    } else if *t == pong_topic {
+                        traffic
+                            .receive::<PongMsg>(&traffic.pong_in, &msg.payload)
+                            .and_then(|p| clock.on_pong(&p, now_ms()))
+                            .map(NetworkEvent::ClockSync)
+                    } else if *t == topics.chat() {
+                        traffic
+                            .receive::<ChatMsg>(&traffic.chat_in, &msg.payload)
+                            .map(NetworkEvent::Chat)
+                    } else if *t == claim_topic {
+                        claim_sent = None;
+                        match serde_json::from_slice::<ClaimMsg>(&msg.payload) {
+                            Ok(claim) if claim.granted => {
+                                granted = true;
+                                None
+                            }
+                            Ok(claim) => {
+                                // Someone else holds the slot: report it and leave quietly
+                                event_tx
+                                    .send(NetworkEvent::SlotTaken {
+                                        requested: claim.player,
+                                        free: claim.free,
+                                    })
+                                    .ok();
+                                claimed = false;
+                                left = true;
+                                break;
+                            }
+                            Err(_) => None,
+                        }
+                    } else {
+                        None
+                    };
+                    if let Some(event) = event {
+                        if event_tx.send(event).is_err() {
+                            left = true;
+                            break;
                         }
                     }
                 }
                 Err(_) => {
-                    event_tx.send(NetworkEvent::Disconnected).ok();
-                    break;
+                    online.store(false, Ordering::Relaxed);
+                    attempt += 1;
+                    let Some(delay) = reconnect_delay(attempt) else {
+                        event_tx.send(NetworkEvent::Disconnected).ok();
+                        break;
+                    };
+                    if event_tx.send(NetworkEvent::Reconnecting(attempt)).is_err() {
+                        break; // offline, so nothing to take down
+                    }
+                    thread::sleep(delay);
+                    if is_player {
+                        connection_no += 1;
+                        let will = leave_payload(&session, connection_no);
+                        let will = LastWill::new(topics.leave(), will, QoS::AtLeastOnce, false);
+                        connection.eventloop.mqtt_options.set_last_will(will);
+                    }
                 }
                 _ => {}
             }
+
+            if claim_sent.is_some_and(|sent| now_ms().saturating_sub(sent) > CLAIM_TIMEOUT_MS) {
+                claim_sent = None;
+                granted = true;
+            }
+            if granted && !claimed {
+                claimed = true;
+                if let (Some(queues), Some(player)) = (queues.take(), config.role.player()) {
+                    spawn_publishers(&client, &topics, player, queues, traffic.clone());
+
+                    // Send a restart request as soon as we first hold the slot.
+                    // If the game is in 'ended' state (stale session on server), this resets it.
+                    // If the game is 'waiting' or 'playing', the server ignores it.
+                    // Not on reconnects: that could wipe a game-over the opponent is looking at.
+                    if let Ok(payload) = serde_json::to_vec(&RestartMsg { timestamp: now_ms() }) {
+                        client.try_publish(topics.restart(), QoS::AtMostOnce, false, payload).ok();
+                    }
+                }
+                announce(&lobby_status);
+            } else if claimed
+                && online.load(Ordering::Relaxed)
+                && now_ms().saturating_sub(announced_at.get()) >= LOBBY_REFRESH_MS
+            {
+                announce(&lobby_status);
+            }
+        }
+        drop(ping_stop);
+
+        // Left the game: free the slot and take our lobby announcement down (a
+        // clean disconnect doesn't fire the last will), then say goodbye
+        if left {
+            if claimed {
+                let payload = leave_payload(&session, connection_no);
+                client
+                    .try_publish(topics.leave(), QoS::AtLeastOnce, false, payload)
+                    .ok();
+                if let Some(topic) = &lobby_topic {
+                    client.try_publish(topic, QoS::AtLeastOnce, true, Vec::new()).ok();
+                }
+            }
+            client.try_disconnect().ok();
+            for notification in connection.iter().take(16) {
+                match notification {
+                    Ok(rumqttc::Event::Outgoing(rumqttc::Outgoing::Disconnect)) | Err(_) => break,
+                    _ => {}
+                }
+            }
         }
     });
 
-    NetworkHandle {
+    Ok(NetworkHandle {
         rx: event_rx,
         paddle_tx,
         serve_tx,
         restart_tx,
         ready_tx,
-    }
+        chat_tx,
+        traffic,
+    })
+}
+
+/// Receiving ends of the `NetworkHandle` senders
+struct PublishQueues {
+    paddle_rx: mpsc::Receiver<f32>,
+    serve_rx: mpsc::Receiver<()>,
+    restart_rx: mpsc::Receiver<()>,
+    ready_rx: mpsc::Receiver<()>,
+}
+
+/// One sub-thread per outgoing message type, each running until the game drops its sender
+fn spawn_publishers(
+    client: &Client,
+    topics: &Topics,
+    player: u8,
+    queues: PublishQueues,
+    traffic: Arc<TrafficCounters>,
+) {
+    let PublishQueues {
+        paddle_rx,
+        serve_rx,
+        restart_rx,
+        ready_rx,
+    } = queues;
+
+    // Spawn a sub-thread to forward outgoing paddle positions (physics units)
+    let publish_client = client.clone();
+    let my_paddle_topic = if player == 1 { topics.p1_paddle() } else { topics.p2_paddle() };
+    thread::spawn(move || {
+        while let Ok(y) = paddle_rx.recv() {
+            let msg = PaddleMsg {
+                y,
+                timestamp: now_ms(),
+            };
+            if let Ok(payload) = serde_json::to_vec(&msg) {
+                if publish_client
+                    .publish(&my_paddle_topic, QoS::AtMostOnce, true, payload)
+                    .is_ok()
+                {
+                    traffic.paddle_out.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    });
+
+    // Spawn a sub-thread to forward serve signals
+    let serve_client = client.clone();
+    let serve_topic = topics.serve();
+    thread::spawn(move || {
+        while let Ok(()) = serve_rx.recv() {
+            if let Ok(payload) = serde_json::to_vec(&ServeMsg { player, timestamp: now_ms() }) {
+                serve_client.publish(&serve_topic, QoS::AtMostOnce, false, payload).ok();
+            }
+        }
+    });
+
+    // Spawn a sub-thread to forward restart signals
+    let restart_client = client.clone();
+    let restart_topic = topics.restart();
+    thread::spawn(move || {
+        while let Ok(()) = restart_rx.recv() {
+            if let Ok(payload) = serde_json::to_vec(&RestartMsg { timestamp: now_ms() }) {
+                restart_client.publish(&restart_topic, QoS::AtMostOnce, false, payload).ok();
+            }
+        }
+    });
+
+    // Spawn a sub-thread to forward ready signals (post-game restart coordination)
+    let ready_client = client.clone();
+    let ready_topic = topics.ready();
+    thread::spawn(move || {
+        while let Ok(()) = ready_rx.recv() {
+            if let Ok(payload) = serde_json::to_vec(&ReadyMsg { player, timestamp: now_ms() }) {
+                ready_client.publish(&ready_topic, QoS::AtMostOnce, false, payload).ok();
+            }
+        }
+    });
 }
 
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn now_ms() -> u64 {
+pub fn now_ms() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis() as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Server clock runs an hour ahead of ours.
+    const SKEW: u64 = 3_600_000;
+    /// The server holds each ping this long before answering.
+    const HOLD: u64 = 2;
+
+    /// A ping sent at local time `t0` that takes `up` ms to reach the server
+    /// and whose pong takes `down` ms back; returns the pong and its arrival time.
+    fn exchange(t0: u64, up: u64, down: u64) -> (PongMsg, u64) {
+        let received = t0 + SKEW + up;
+        let pong = PongMsg {
+            seq: 0,
+            ping_timestamp: t0,
+            received,
+            timestamp: received + HOLD,
+        };
+        (pong, t0 + up + HOLD + down)
+    }
+
+    fn feed(clock: &mut ClockEstimator, t0: u64, up: u64, down: u64) -> Option<ClockSync> {
+        let (pong, local) = exchange(t0, up, down);
+        clock.on_pong(&pong, local)
+    }
+
+    #[test]
+    fn symmetric_delay_gives_exact_offset() {
+        let sync = feed(&mut ClockEstimator::default(), 10_000, 20, 20).unwrap();
+        assert_eq!(sync.rtt_ms, 40.0);
+        assert_eq!(sync.offset_ms, SKEW as f64);
+    }
+
+    #[test]
+    fn asymmetric_delay_is_off_by_half_the_difference() {
+        let sync = feed(&mut ClockEstimator::default(), 10_000, 10, 50).unwrap();
+        // The server's hold time isn't part of the round trip
+        assert_eq!(sync.rtt_ms, 60.0);
+        assert_eq!(sync.offset_ms, SKEW as f64 - 20.0);
+    }
+
+    #[test]
+    fn offset_comes_from_the_fastest_recent_sample() {
+        let mut clock = ClockEstimator::default();
+        // Pong queued on the way back: slow and lopsided
+        let sync = feed(&mut clock, 0, 10, 90).unwrap();
+        assert_eq!(sync.offset_ms, SKEW as f64 - 40.0);
+        // A quick, even exchange wins...
+        let sync = feed(&mut clock, 1_000, 10, 10).unwrap();
+        assert_eq!(sync.offset_ms, SKEW as f64);
+        // ...and stays picked while slower ones come in
+        for i in 2..=CLOCK_SAMPLES as u64 {
+            let sync = feed(&mut clock, i * 1_000, 10, 90).unwrap();
+            assert_eq!(sync.offset_ms, SKEW as f64);
+        }
+        // Until it is pushed out of the window
+        let sync = feed(&mut clock, 9_000, 10, 90).unwrap();
+        assert_eq!(sync.offset_ms, SKEW as f64 - 40.0);
+    }
+
+    #[test]
+    fn rtt_and_jitter_are_smoothed() {
+        let mut clock = ClockEstimator::default();
+        let sync = feed(&mut clock, 0, 20, 20).unwrap();
+        assert_eq!((sync.rtt_ms, sync.jitter_ms), (40.0, 0.0));
+        let sync = feed(&mut clock, 1_000, 40, 40).unwrap();
+        assert_eq!(sync.rtt_ms, 40.0 + (80.0 - 40.0) / 8.0);
+        assert_eq!(sync.jitter_ms, 40.0 / 16.0);
+    }
+
+    #[test]
+    fn stale_and_impossible_replies_are_dropped() {
+        let mut clock = ClockEstimator::default();
+        let (pong, _) = exchange(10_000, 20, 20);
+        // Answer to a ping from before a long stall
+        assert_eq!(clock.on_pong(&pong, 10_000 + PING_TIMEOUT_MS + 1), None);
+        // Arrived "before" it was sent
+        assert_eq!(clock.on_pong(&pong, 9_000), None);
+        assert!(clock.samples.is_empty());
+    }
+
+    fn endpoint(host: &str, port: u16, transport: Transport, tls: bool) -> BrokerEndpoint {
+        BrokerEndpoint {
+            host: host.to_string(),
+            port,
+            transport,
+            ws_path: DEFAULT_WS_PATH.to_string(),
+            tls,
+        }
+    }
+
+    #[test]
+    fn parse_picks_transport_tls_and_port_by_scheme() {
+        let parse = |address| BrokerEndpoint::parse(address, 1884).unwrap();
+        assert_eq!(
+            parse("broker"),
+            endpoint("broker", 1884, Transport::Tcp, false)
+        );
+        assert_eq!(
+            parse("mqtt://broker"),
+            endpoint("broker", 1883, Transport::Tcp, false)
+        );
+        assert_eq!(
+            parse("tcp://broker"),
+            endpoint("broker", 1883, Transport::Tcp, false)
+        );
+        assert_eq!(
+            parse("mqtts://broker"),
+            endpoint("broker", 8883, Transport::Tcp, true)
+        );
+        assert_eq!(
+            parse("SSL://broker"),
+            endpoint("broker", 8883, Transport::Tcp, true)
+        );
+        assert_eq!(
+            parse("ws://broker"),
+            endpoint("broker", 80, Transport::WebSocket, false)
+        );
+        assert_eq!(
+            parse("wss://broker"),
+            endpoint("broker", 443, Transport::WebSocket, true)
+        );
+    }
+
+    #[test]
+    fn parse_takes_an_explicit_port_and_path() {
+        let parsed = BrokerEndpoint::parse(" wss://broker:8443/ws ", 1883).unwrap();
+        assert_eq!((parsed.host.as_str(), parsed.port), ("broker", 8443));
+        assert_eq!(parsed.ws_path, "/ws");
+        assert_eq!(BrokerEndpoint::parse("broker:", 1883).unwrap().port, 1883);
+        assert_eq!(
+            BrokerEndpoint::parse("10.0.0.5:1999", 1883).unwrap().port,
+            1999
+        );
+    }
+
+    #[test]
+    fn parse_rejects_what_it_would_otherwise_guess() {
+        let error = |address| BrokerEndpoint::parse(address, 1883).unwrap_err();
+        assert_eq!(
+            error("mqqts://broker"),
+            "unknown scheme 'mqqts' (mqtt, mqtts, ws, wss)"
+        );
+        assert_eq!(
+            error("https://broker"),
+            "unknown scheme 'https' (mqtt, mqtts, ws, wss)"
+        );
+        assert_eq!(error("broker:88a3"), "invalid port '88a3'");
+        assert_eq!(error("broker:70000"), "invalid port '70000'");
+        assert_eq!(error("mqtts://:8883"), "missing host");
+    }
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        let custom_path = BrokerEndpoint {
+            ws_path: "/mqtt/v2".to_string(),
+            ..endpoint("broker", 8080, Transport::WebSocket, false)
+        };
+        for original in [
+            endpoint("broker", 1999, Transport::Tcp, false),
+            endpoint("10.0.0.5", 8883, Transport::Tcp, true),
+            endpoint("broker", 443, Transport::WebSocket, true),
+            custom_path,
+        ] {
+            let text = original.to_string();
+            assert_eq!(
+                BrokerEndpoint::parse(&text, 1883).unwrap(),
+                original,
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn reconnect_backs_off_exponentially_then_gives_up() {
+        let ms = |attempt| reconnect_delay(attempt).map(|d| d.as_millis());
+        let expected = [
+            500, 1_000, 2_000, 4_000, 8_000, 16_000, 16_000, 16_000, 16_000, 16_000,
+        ];
+        for (attempt, delay) in (1..).zip(expected) {
+            assert_eq!(ms(attempt), Some(delay), "attempt {}", attempt);
+        }
+        assert_eq!(MAX_RECONNECT_ATTEMPTS, 10);
+        assert_eq!(ms(MAX_RECONNECT_ATTEMPTS + 1), None);
+        assert_eq!(ms(u32::MAX), None);
+    }
+}
@@ -0,0 +1,684 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::network::{now_ms, BallMsg, ChatMsg, ClockSync, GameStatus, NetworkEvent, StateMsg};
+
+// ---------------------------------------------------------------------------
+// Match recording
+//
+// A replay is a JSON Lines file: one `ReplayHeader`, then one `ReplayRecord`
+// per network event or local paddle publish, each stamped with our clock when
+// it happened (ms since the epoch). Messages keep their own timestamps too.
+//
+//   {"format":"rust-pong-replay","version":1,"game_id":"demo",...}
+//   {"t":1718000000123,"event":"ball","x":20.0,"y":6.0,...}
+//   {"t":1718000000140,"event":"local_paddle","player":1,"y":5.5}
+//
+// Files go to <data dir>/rust-pong/replays, named by start time and game ID.
+// ---------------------------------------------------------------------------
+
+pub const REPLAY_FORMAT: &str = "rust-pong-replay";
+/// Bumped whenever a record changes shape; readers refuse newer versions.
+pub const REPLAY_VERSION: u32 = 1;
+const REPLAY_DIR_NAME: &str = "replays";
+const REPLAY_EXTENSION: &str = "jsonl";
+/// Overrides the replays directory
+const REPLAY_DIR_ENV: &str = "PONG_REPLAY_DIR";
+
+/// First line of every replay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub format: String,
+    pub version: u32,
+    pub game_id: String,
+    /// Slot we played; None when spectating
+    pub player: Option<u8>,
+    /// Our profile name
+    pub name: String,
+    /// When recording started (our clock), ms
+    pub started: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ReplayEvent {
+    /// A remote paddle, as received
+    Paddle { player: u8, y: f32, timestamp: u64 },
+    /// Our own paddle, as published
+    LocalPaddle { player: u8, y: f32 },
+    Ball(BallMsg),
+    State(StateMsg),
+    Chat(ChatMsg),
+    Clock(ClockSync),
+    Connected,
+    Reconnecting { attempt: u32 },
+    Disconnected,
+    SlotTaken { requested: u8, free: Option<u8> },
+}
+
+impl From<&NetworkEvent> for ReplayEvent {
+    fn from(event: &NetworkEvent) -> Self {
+        match event {
+            NetworkEvent::Paddle(player, msg) => ReplayEvent::Paddle {
+                player: *player,
+                y: msg.y,
+                timestamp: msg.timestamp,
+            },
+            NetworkEvent::BallUpdate(msg) => ReplayEvent::Ball(msg.clone()),
+            NetworkEvent::StateUpdate(msg) => ReplayEvent::State(msg.clone()),
+            NetworkEvent::Chat(msg) => ReplayEvent::Chat(msg.clone()),
+            NetworkEvent::ClockSync(sync) => ReplayEvent::Clock(*sync),
+            NetworkEvent::Connected => ReplayEvent::Connected,
+            NetworkEvent::Reconnecting(attempt) => ReplayEvent::Reconnecting { attempt: *attempt },
+            NetworkEvent::Disconnected => ReplayEvent::Disconnected,
+            NetworkEvent::SlotTaken { requested, free } => ReplayEvent::SlotTaken {
+                requested: *requested,
+                free: *free,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayRecord {
+    /// Our clock, ms
+    pub t: u64,
+    #[serde(flatten)]
+    pub event: ReplayEvent,
+}
+
+pub fn replays_dir() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(REPLAY_DIR_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    dirs::data_dir().map(|dir| dir.join("rust-pong").join(REPLAY_DIR_NAME))
+}
+
+/// Writes one game to a new file in the replays directory. Write errors after
+/// the file is created are ignored, like other best-effort I/O in the client.
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    /// Start `<replays dir>/<started>-<game_id>.jsonl` and write its header.
+    pub fn create(game_id: &str, player: Option<u8>, name: &str) -> io::Result<Recorder> {
+        let dir = replays_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        fs::create_dir_all(&dir)?;
+
+        let started = now_ms();
+        // Game IDs are typed by users; keep the file name portable
+        let safe_id: String = game_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        let path = dir.join(format!("{}-{}.{}", started, safe_id, REPLAY_EXTENSION));
+
+        let header = ReplayHeader {
+            format: REPLAY_FORMAT.to_string(),
+            version: REPLAY_VERSION,
+            game_id: game_id.to_string(),
+            player,
+            name: name.to_string(),
+            started,
+        };
+        let mut out = BufWriter::new(File::create(&path)?);
+        serde_json::to_writer(&mut out, &header)?;
+        out.write_all(b"\n")?;
+        Ok(Recorder { out })
+    }
+
+    pub fn record_event(&mut self, event: &NetworkEvent) {
+        self.write(ReplayEvent::from(event));
+        // Score changes are rare; make sure they reach the disk even if we crash
+        if matches!(event, NetworkEvent::StateUpdate(_)) {
+            self.out.flush().ok();
+        }
+    }
+
+    pub fn record_local_paddle(&mut self, player: u8, y: f32) {
+        self.write(ReplayEvent::LocalPaddle { player, y });
+    }
+
+    fn write(&mut self, event: ReplayEvent) {
+        let record = ReplayRecord { t: now_ms(), event };
+        if serde_json::to_writer(&mut self.out, &record).is_ok() {
+            self.out.write_all(b"\n").ok();
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.out.flush().ok();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Playback
+//
+// A loaded replay is split into per-kind timelines so any moment can be drawn
+// without replaying from the start: seeking is just moving the clock. Ball
+// positions are interpolated between packets; paddles and scores hold their
+// last value. Rallies start when the resting ball is served, points when a
+// score changes.
+// ---------------------------------------------------------------------------
+
+pub const PLAYBACK_SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+const NORMAL_SPEED: usize = 2;
+/// Ball packets further apart than this (or jumping further) are not interpolated
+const MAX_INTERPOLATION_GAP_MS: u64 = 200;
+const MAX_INTERPOLATION_DISTANCE: f32 = 4.0;
+
+/// A file in the replays directory, with its header
+#[derive(Debug, Clone)]
+pub struct ListedReplay {
+    pub path: PathBuf,
+    pub header: ReplayHeader,
+}
+
+/// Replays in the replays directory, newest first. Files without a readable
+/// header are left out; a missing directory is an empty list.
+pub fn list_replays() -> io::Result<Vec<ListedReplay>> {
+    let Some(dir) = replays_dir() else {
+        return Ok(Vec::new());
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut replays: Vec<ListedReplay> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == REPLAY_EXTENSION))
+        .filter_map(|path| {
+            let mut first = String::new();
+            BufReader::new(File::open(&path).ok()?).read_line(&mut first).ok()?;
+            let header = parse_header(&first).ok()?;
+            Some(ListedReplay { path, header })
+        })
+        .collect();
+    replays.sort_by_key(|r| std::cmp::Reverse(r.header.started));
+    Ok(replays)
+}
+
+fn parse_header(line: &str) -> io::Result<ReplayHeader> {
+    let header: ReplayHeader = serde_json::from_str(line)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("bad header: {}", e)))?;
+    if header.format != REPLAY_FORMAT {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a rust-pong replay"));
+    }
+    if header.version > REPLAY_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("replay version {} is newer than this client", header.version),
+        ));
+    }
+    Ok(header)
+}
+
+/// What the court looked like at one moment of a replay
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayFrame {
+    /// Ball top-left, court units
+    pub ball: Option<(f32, f32)>,
+    /// Paddle tops, court units
+    pub paddles: [Option<f32>; 2],
+    pub scores: (u32, u32),
+    pub rally: u32,
+    pub names: [Option<String>; 2],
+    pub status: Option<GameStatus>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    /// First and last record, our clock (ms)
+    start: u64,
+    end: u64,
+    balls: Vec<(u64, BallMsg)>,
+    paddles: [Vec<(u64, f32)>; 2],
+    states: Vec<(u64, StateMsg)>,
+    /// Serves: the ball leaving its resting place
+    rallies: Vec<u64>,
+    /// Score changes
+    points: Vec<u64>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        Replay::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Lines that don't parse (e.g. one cut short by a crash) are skipped.
+    pub fn parse(text: &str) -> io::Result<Replay> {
+        let mut lines = text.lines();
+        let header = parse_header(lines.next().unwrap_or_default())?;
+        let mut replay = Replay {
+            start: header.started,
+            end: header.started,
+            header,
+            balls: Vec::new(),
+            paddles: [Vec::new(), Vec::new()],
+            states: Vec::new(),
+            rallies: Vec::new(),
+            points: Vec::new(),
+        };
+        for record in lines.filter_map(|line| serde_json::from_str::<ReplayRecord>(line).ok()) {
+            replay.push(record);
+        }
+        Ok(replay)
+    }
+
+    fn push(&mut self, ReplayRecord { t, event }: ReplayRecord) {
+        // Records are written in order, but don't trust the clock not to step back
+        let t = t.max(self.end);
+        self.end = t;
+        match event {
+            ReplayEvent::Ball(msg) => {
+                let was_resting = self
+                    .balls
+                    .last()
+                    .is_none_or(|(_, prev)| prev.dx == 0.0 && prev.dy == 0.0);
+                if was_resting && (msg.dx != 0.0 || msg.dy != 0.0) {
+                    self.rallies.push(t);
+                }
+                self.balls.push((t, msg));
+            }
+            ReplayEvent::Paddle { player, y, .. } | ReplayEvent::LocalPaddle { player, y } => {
+                if let Some(track) = self.paddles.get_mut(player.wrapping_sub(1) as usize) {
+                    track.push((t, y));
+                }
+            }
+            ReplayEvent::State(msg) => {
+                let prev = self.states.last().map(|(_, s)| (s.p1_score, s.p2_score));
+                let now = (msg.p1_score, msg.p2_score);
+                if prev.is_some_and(|p| p != now && now != (0, 0)) {
+                    self.points.push(t);
+                }
+                self.states.push((t, msg));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    pub fn rallies(&self) -> usize {
+        self.rallies.len()
+    }
+
+    /// The court at `t` (our clock, ms)
+    pub fn frame_at(&self, t: u64) -> ReplayFrame {
+        let state = latest(&self.states, t);
+        ReplayFrame {
+            ball: self.ball_at(t),
+            paddles: [0, 1].map(|i| latest(&self.paddles[i], t).copied()),
+            scores: state.map_or((0, 0), |s| (s.p1_score, s.p2_score)),
+            rally: state.map_or(0, |s| s.rally),
+            names: [
+                state.and_then(|s| s.p1_name.clone()),
+                state.and_then(|s| s.p2_name.clone()),
+            ],
+            status: state.map(|s| s.status.clone()),
+        }
+    }
+
+    fn ball_at(&self, t: u64) -> Option<(f32, f32)> {
+        let next = self.balls.partition_point(|(bt, _)| *bt <= t);
+        let Some(i) = next.checked_sub(1) else {
+            // Before the first packet: show where it will be
+            return self.balls.first().map(|(_, b)| (b.x, b.y));
+        };
+        let (t0, b0) = &self.balls[i];
+        let Some((t1, b1)) = self.balls.get(next) else {
+            return Some((b0.x, b0.y));
+        };
+        let jump = (b1.x - b0.x).abs().max((b1.y - b0.y).abs());
+        if t1 - t0 > MAX_INTERPOLATION_GAP_MS || jump > MAX_INTERPOLATION_DISTANCE || t1 == t0 {
+            return Some((b0.x, b0.y));
+        }
+        let f = (t - t0) as f32 / (t1 - t0) as f32;
+        Some((b0.x + (b1.x - b0.x) * f, b0.y + (b1.y - b0.y) * f))
+    }
+}
+
+/// Latest value at or before `t`, or the first one if `t` is earlier than all of them
+fn latest<T>(track: &[(u64, T)], t: u64) -> Option<&T> {
+    let next = track.partition_point(|(at, _)| *at <= t);
+    track.get(next.saturating_sub(1)).map(|(_, v)| v)
+}
+
+/// Next boundary after `t`, or the last one before it
+fn seek_in(marks: &[u64], t: u64, forward: bool) -> Option<u64> {
+    if forward {
+        marks.iter().copied().find(|&m| m > t)
+    } else {
+        marks.iter().copied().rev().find(|&m| m < t)
+    }
+}
+
+/// Playback clock over a `Replay`: pause, speed, frame step and seeking
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    /// Position on the replay's clock, ms
+    at: f64,
+    speed: usize,
+    paused: bool,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            at: replay.start as f64,
+            replay,
+            speed: NORMAL_SPEED,
+            paused: false,
+        }
+    }
+
+    /// Move the clock on by `elapsed` real time. Stops at the end.
+    pub fn advance(&mut self, elapsed: Duration) {
+        if self.paused {
+            return;
+        }
+        self.at += elapsed.as_secs_f64() * 1000.0 * PLAYBACK_SPEEDS[self.speed];
+        if self.at >= self.replay.end as f64 {
+            self.at = self.replay.end as f64;
+            self.paused = true;
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.paused && self.at >= self.replay.end as f64 {
+            // Finished: play again from the top
+            self.at = self.replay.start as f64;
+        }
+        self.paused = !self.paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn speed(&self) -> f64 {
+        PLAYBACK_SPEEDS[self.speed]
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(PLAYBACK_SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    fn now(&self) -> u64 {
+        self.at as u64
+    }
+
+    fn seek(&mut self, t: Option<u64>, forward: bool) {
+        let fallback = if forward { self.replay.end } else { self.replay.start };
+        self.at = t.unwrap_or(fallback) as f64;
+    }
+
+    /// Pause and move one ball packet (one server tick) forwards or back
+    pub fn step(&mut self, forward: bool) {
+        self.paused = true;
+        let balls = &self.replay.balls;
+        let now = self.now();
+        let t = if forward {
+            balls.get(balls.partition_point(|(t, _)| *t <= now))
+        } else {
+            balls
+                .partition_point(|(t, _)| *t < now)
+                .checked_sub(1)
+                .and_then(|i| balls.get(i))
+        };
+        self.seek(t.map(|(t, _)| *t), forward);
+    }
+
+    /// To the next serve, or back to the start of this (then the previous) rally
+    pub fn seek_rally(&mut self, forward: bool) {
+        self.seek(seek_in(&self.replay.rallies, self.now(), forward), forward);
+    }
+
+    /// To the moment the next (or previous) point was scored
+    pub fn seek_point(&mut self, forward: bool) {
+        self.seek(seek_in(&self.replay.points, self.now(), forward), forward);
+    }
+
+    pub fn restart(&mut self) {
+        self.at = self.replay.start as f64;
+    }
+
+    /// Playback position and length, ms
+    pub fn position_ms(&self) -> u64 {
+        self.now() - self.replay.start
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        self.replay.end - self.replay.start
+    }
+
+    /// Which rally we're in (1-based; 0 before the first serve)
+    pub fn rally(&self) -> usize {
+        self.replay.rallies.partition_point(|&t| t <= self.now())
+    }
+
+    pub fn frame(&self) -> ReplayFrame {
+        self.replay.frame_at(self.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = concat!(
+        r#"{"format":"rust-pong-replay","version":1,"#,
+        r#""game_id":"demo","player":1,"name":"me","started":1000}"#
+    );
+
+    fn line(t: u64, event: ReplayEvent) -> String {
+        serde_json::to_string(&ReplayRecord { t, event }).unwrap()
+    }
+
+    fn ball(t: u64, x: f32, dx: f32) -> String {
+        let msg = BallMsg {
+            x,
+            y: 5.0,
+            dx,
+            dy: 0.0,
+            timestamp: t,
+        };
+        line(t, ReplayEvent::Ball(msg))
+    }
+
+    fn state(t: u64, p1_score: u32, p2_score: u32) -> String {
+        let msg = StateMsg {
+            p1_score,
+            p2_score,
+            p1_name: None,
+            p2_name: None,
+            status: GameStatus::Playing,
+            rally: 0,
+            ramp: None,
+            timestamp: t,
+        };
+        line(t, ReplayEvent::State(msg))
+    }
+
+    /// Two rallies, served at 1100 and 2000, won at 1500 and 2500
+    fn two_rallies() -> ReplayPlayer {
+        let lines = [
+            HEADER.to_string(),
+            state(1000, 0, 0),
+            ball(1000, 19.5, 0.0),
+            ball(1100, 19.5, 0.35),
+            ball(1116, 19.85, 0.35),
+            ball(1133, 20.2, 0.35),
+            state(1500, 1, 0),
+            ball(1500, 19.5, 0.0),
+            ball(2000, 19.5, -0.35),
+            ball(2016, 19.15, -0.35),
+            line(2016, ReplayEvent::LocalPaddle { player: 1, y: 4.0 }),
+            // Cut short by a crash
+            r#"{"t":2020,"event":"ball","x":"#.to_string(),
+            state(2500, 1, 1),
+            line(
+                3000,
+                ReplayEvent::Paddle {
+                    player: 2,
+                    y: 6.0,
+                    timestamp: 3000,
+                },
+            ),
+        ];
+        ReplayPlayer::new(Replay::parse(&lines.join("\n")).unwrap())
+    }
+
+    #[test]
+    fn indexes_rallies_and_points() {
+        let player = two_rallies();
+        assert_eq!(player.replay.rallies, vec![1100, 2000]);
+        assert_eq!(player.replay.points, vec![1500, 2500]);
+        assert_eq!((player.replay.start(), player.replay.end()), (1000, 3000));
+        assert_eq!(player.duration_ms(), 2000);
+    }
+
+    #[test]
+    fn rejects_foreign_or_newer_files() {
+        assert!(Replay::parse(&HEADER.replace("rust-pong-replay", "other")).is_err());
+        assert!(Replay::parse(&HEADER.replace(r#""version":1"#, r#""version":99"#)).is_err());
+        assert!(Replay::parse("").is_err());
+    }
+
+    #[test]
+    fn frame_interpolates_the_ball_and_holds_the_rest() {
+        let mut player = two_rallies();
+        player.seek(Some(1108), true);
+        let frame = player.frame();
+        let (x, _) = frame.ball.unwrap();
+        assert!((x - 19.675).abs() < 1e-4, "{x}");
+        assert_eq!(frame.scores, (0, 0));
+
+        player.seek(Some(2600), true);
+        let frame = player.frame();
+        assert_eq!(frame.scores, (1, 1));
+        // P2's paddle hasn't moved yet: it is shown where it first appears
+        assert_eq!(frame.paddles, [Some(4.0), Some(6.0)]);
+    }
+
+    #[test]
+    fn steps_one_ball_packet_at_a_time() {
+        let mut player = two_rallies();
+        player.seek(Some(1100), true);
+        player.step(true);
+        assert!(player.is_paused());
+        assert_eq!(player.now(), 1116);
+        player.step(true);
+        assert_eq!(player.now(), 1133);
+        player.step(false);
+        assert_eq!(player.now(), 1116);
+
+        // Between packets, back goes to the one before
+        player.seek(Some(1120), true);
+        player.step(false);
+        assert_eq!(player.now(), 1116);
+    }
+
+    #[test]
+    fn step_clamps_at_both_ends() {
+        let mut player = two_rallies();
+        player.step(false);
+        assert_eq!(player.now(), player.replay.start());
+        player.seek(Some(2016), true);
+        player.step(true);
+        assert_eq!(player.now(), player.replay.end());
+    }
+
+    #[test]
+    fn seeks_between_rallies() {
+        let mut player = two_rallies();
+        player.seek_rally(true);
+        assert_eq!((player.now(), player.rally()), (1100, 1));
+        player.seek_rally(true);
+        assert_eq!((player.now(), player.rally()), (2000, 2));
+
+        // Mid-rally, back goes to the start of this rally...
+        player.seek(Some(2300), true);
+        player.seek_rally(false);
+        assert_eq!(player.now(), 2000);
+        // ...and from its start, to the previous one
+        player.seek_rally(false);
+        assert_eq!(player.now(), 1100);
+        // Past either end: clamp
+        player.seek_rally(false);
+        assert_eq!((player.now(), player.rally()), (1000, 0));
+        player.seek(Some(2000), true);
+        player.seek_rally(true);
+        assert_eq!(player.now(), player.replay.end());
+    }
+
+    #[test]
+    fn seeks_between_points() {
+        let mut player = two_rallies();
+        player.seek_point(true);
+        assert_eq!(player.now(), 1500);
+        assert_eq!(player.frame().scores, (1, 0));
+        player.seek_point(true);
+        assert_eq!(player.now(), 2500);
+        player.seek_point(false);
+        assert_eq!(player.now(), 1500);
+        player.seek_point(false);
+        assert_eq!(player.now(), player.replay.start());
+    }
+
+    #[test]
+    fn speed_scales_playback() {
+        let mut player = two_rallies();
+        player.advance(Duration::from_millis(100));
+        assert_eq!(player.position_ms(), 100);
+
+        player.faster();
+        player.faster();
+        player.faster();
+        assert_eq!(player.speed(), PLAYBACK_SPEEDS[PLAYBACK_SPEEDS.len() - 1]);
+        player.advance(Duration::from_millis(100));
+        assert_eq!(player.position_ms(), 100 + 400);
+
+        for _ in 0..PLAYBACK_SPEEDS.len() {
+            player.slower();
+        }
+        assert_eq!(player.speed(), PLAYBACK_SPEEDS[0]);
+        player.advance(Duration::from_millis(100));
+        assert_eq!(player.position_ms(), 500 + 25);
+    }
+
+    #[test]
+    fn playback_stops_at_the_end_and_restarts_on_play() {
+        let mut player = two_rallies();
+        player.advance(Duration::from_secs(10));
+        assert!(player.is_paused());
+        assert_eq!(player.now(), player.replay.end());
+        // Paused playback doesn't move
+        player.advance(Duration::from_secs(1));
+        assert_eq!(player.now(), player.replay.end());
+        player.toggle_pause();
+        assert!(!player.is_paused());
+        assert_eq!(player.position_ms(), 0);
+    }
+}
@@ -0,0 +1,838 @@
+use rumqttc::{Client, QoS};
+use std::collections::HashMap;
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::game::{
+    COURT_BALL_SIZE, COURT_HEIGHT, COURT_PADDLE_FACE_X, COURT_PADDLE_HEIGHT, COURT_WIDTH,
+    PLAYER_NAME_CHAR_LEN,
+};
+use crate::network::{
+    self, now_ms, BallMsg, ClaimMsg, GameStatus, JoinMsg, LeaveMsg, NetworkConfig, PaddleMsg,
+    PingMsg, PongMsg, ReadyMsg, StateMsg, Topics, Transport,
+};
+use crate::sim::{deflect, PaddleMotion, SpeedRamp};
+use crate::tls::TlsSettings;
+
+// ---------------------------------------------------------------------------
+// Rules / physics constants (court units, per server tick)
+// ---------------------------------------------------------------------------
+
+pub const SERVER_TICK_HZ: u64 = 60;
+const WINNING_SCORE: u32 = 5;
+const BALL_SPEED_X: f32 = 0.35;
+const BALL_SPEED_Y: f32 = 0.15;
+/// Ball rests at center this long after a point unless someone serves first.
+const SERVE_DELAY_TICKS: u32 = 90;
+/// Re-publish state at least this often so late joiners pick it up.
+const STATE_REPUBLISH_TICKS: u32 = 60;
+/// Forget games nobody has touched for ten minutes.
+const MATCH_IDLE_TICKS: u32 = 10 * 60 * SERVER_TICK_HZ as u32;
+
+// ---------------------------------------------------------------------------
+// Per-game authoritative state
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct Match {
+    /// Paddle tops in court units (0 .. COURT_HEIGHT - COURT_PADDLE_HEIGHT)
+    pub paddles: [f32; 2],
+    pub ball: BallMsg,
+    pub scores: [u32; 2],
+    pub status: GameStatus,
+    joined: [bool; 2],
+    /// Session tag of the client holding each slot (clients that send one)
+    owners: [Option<String>; 2],
+    /// Latest connection each owner joined from (see `on_leave`)
+    connections: [u32; 2],
+    /// Profile names from the joins, relayed in every state message
+    names: [Option<String>; 2],
+    ready: [bool; 2],
+    /// Ticks left before the resting ball is launched automatically (0 = rally live)
+    serve_timer: u32,
+    /// Horizontal direction of the next serve (-1.0 = towards P1, 1.0 = towards P2)
+    serve_dir: f32,
+    /// Paddle movement bends returns
+    spin: bool,
+    motion: [PaddleMotion; 2],
+    /// Set by whoever opens the game (see `on_join`)
+    ramp: SpeedRamp,
+    /// Paddle returns since the last serve
+    rally: u32,
+    state_dirty: bool,
+    ticks_since_state: u32,
+    idle_ticks: u32,
+}
+
+impl Default for Match {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Match {
+    pub fn new() -> Self {
+        let paddle_y = (COURT_HEIGHT - COURT_PADDLE_HEIGHT) / 2.0;
+        let mut m = Self {
+            paddles: [paddle_y, paddle_y],
+            ball: BallMsg {
+                x: 0.0,
+                y: 0.0,
+                dx: 0.0,
+                dy: 0.0,
+                timestamp: 0,
+            },
+            scores: [0, 0],
+            status: GameStatus::Waiting,
+            joined: [false, false],
+            owners: [None, None],
+            connections: [0, 0],
+            names: [None, None],
+            ready: [false, false],
+            serve_timer: SERVE_DELAY_TICKS,
+            serve_dir: if rand::random() { 1.0 } else { -1.0 },
+            spin: false,
+            motion: [PaddleMotion::default(); 2],
+            ramp: SpeedRamp::default(),
+            rally: 0,
+            state_dirty: true,
+            ticks_since_state: 0,
+            idle_ticks: 0,
+        };
+        m.center_ball();
+        m
+    }
+
+    fn center_ball(&mut self) {
+        self.ball.x = (COURT_WIDTH - COURT_BALL_SIZE) / 2.0;
+        self.ball.y = (COURT_HEIGHT - COURT_BALL_SIZE) / 2.0;
+        self.ball.dx = 0.0;
+        self.ball.dy = 0.0;
+    }
+
+    fn start_playing(&mut self) {
+        self.scores = [0, 0];
+        self.ready = [false, false];
+        self.status = GameStatus::Playing;
+        self.serve_timer = SERVE_DELAY_TICKS;
+        self.rally = 0;
+        self.center_ball();
+        self.state_dirty = true;
+    }
+
+    fn launch_ball(&mut self) {
+        self.serve_timer = 0;
+        self.ball.dx = BALL_SPEED_X * self.serve_dir;
+        self.ball.dy = if rand::random() { BALL_SPEED_Y } else { -BALL_SPEED_Y };
+    }
+
+    /// A client claims slot `player`. Granted if the slot is free or already
+    /// theirs; clients without a session tag (older builds) can't prove the
+    /// latter, so they only get slots nobody owns. The first player into a
+    /// waiting game picks its speed ramp. `connection` counts the client's
+    /// reconnects (0 from clients that don't send it).
+    pub fn on_join(
+        &mut self,
+        player: u8,
+        client: Option<&str>,
+        name: Option<&str>,
+        ramp: Option<SpeedRamp>,
+        connection: u32,
+    ) -> Claim {
+        self.idle_ticks = 0;
+        let idx = player.wrapping_sub(1) as usize;
+        if idx > 1 {
+            return Claim::Taken { free: None };
+        }
+        match (client, &self.owners[idx]) {
+            (Some(client), Some(owner)) if owner == client => {
+                self.connections[idx] = self.connections[idx].max(connection);
+            }
+            (None, None) => {}
+            (Some(client), None) => {
+                self.owners[idx] = Some(client.to_string());
+                self.connections[idx] = connection;
+            }
+            (_, Some(_)) => {
+                let other = 1 - idx;
+                let free = self.owners[other].is_none().then_some(other as u8 + 1);
+                return Claim::Taken { free };
+            }
+        }
+        if let Some(ramp) = ramp {
+            if self.status == GameStatus::Waiting && self.joined == [false, false] {
+                self.ramp = ramp.sanitized();
+            }
+        }
+        self.joined[idx] = true;
+        if let Some(name) = name {
+            self.names[idx] = Some(name.chars().take(PLAYER_NAME_CHAR_LEN).collect());
+        }
+        if self.status == GameStatus::Waiting && self.joined == [true, true] {
+            self.start_playing();
+        }
+        // Always re-announce so a (re)joining client learns the current state
+        self.state_dirty = true;
+        Claim::Granted
+    }
+
+    /// Free whatever slot `client` holds; returns that slot (1 or 2). A leave
+    /// from an older connection than the owner's latest join is the late last
+    /// will of a connection the client has since replaced, and is ignored.
+    pub fn on_leave(&mut self, client: &str, connection: Option<u32>) -> Option<u8> {
+        let idx = self.owners.iter().position(|o| o.as_deref() == Some(client))?;
+        if connection.is_some_and(|c| c < self.connections[idx]) {
+            return None;
+        }
+        self.owners[idx] = None;
+        self.connections[idx] = 0;
+        self.names[idx] = None;
+        self.joined[idx] = false;
+        self.ready[idx] = false;
+        self.state_dirty = true;
+        Some(idx as u8 + 1)
+    }
+
+    pub fn on_paddle(&mut self, player: u8, y: f32) {
+        self.idle_ticks = 0;
+        if let Some(paddle) = self.paddles.get_mut(player.wrapping_sub(1) as usize) {
+            *paddle = y.clamp(0.0, COURT_HEIGHT - COURT_PADDLE_HEIGHT);
+        }
+    }
+
+    pub fn on_serve(&mut self) {
+        self.idle_ticks = 0;
+        if self.status == GameStatus::Playing && self.serve_timer > 0 {
+            self.launch_ball();
+        }
+    }
+
+    pub fn on_restart(&mut self) {
+        self.idle_ticks = 0;
+        if self.status == GameStatus::Ended {
+            if self.joined == [true, true] {
+                self.start_playing();
+            } else {
+                self.scores = [0, 0];
+                self.status = GameStatus::Waiting;
+                self.center_ball();
+                self.state_dirty = true;
+            }
+        }
+    }
+
+    pub fn on_ready(&mut self, player: u8) {
+        self.idle_ticks = 0;
+        if self.status != GameStatus::Ended {
+            return;
+        }
+        if let Some(slot) = self.ready.get_mut(player.wrapping_sub(1) as usize) {
+            *slot = true;
+        }
+        if self.ready == [true, true] {
+            self.start_playing();
+        }
+    }
+
+    /// Advance the match by one server tick.
+    pub fn tick(&mut self) {
+        self.idle_ticks = self.idle_ticks.saturating_add(1);
+        self.ticks_since_state += 1;
+        if self.ticks_since_state >= STATE_REPUBLISH_TICKS {
+            self.state_dirty = true;
+        }
+
+        if self.status != GameStatus::Playing {
+            return;
+        }
+
+        for (motion, &y) in self.motion.iter_mut().zip(&self.paddles) {
+            motion.update(y, 1.0 / SERVER_TICK_HZ as f32);
+        }
+
+        if self.serve_timer > 0 {
+            self.serve_timer -= 1;
+            if self.serve_timer == 0 {
+                self.launch_ball();
+            }
+            return;
+        }
+
+        let spin = |idx: usize| self.spin.then(|| self.motion[idx].speed());
+        let ball = &mut self.ball;
+        ball.x += ball.dx;
+        ball.y += ball.dy;
+
+        // top and bottom walls
+        let max_y = COURT_HEIGHT - COURT_BALL_SIZE;
+        if ball.y <= 0.0 {
+            ball.y = -ball.y;
+            ball.dy = ball.dy.abs();
+        } else if ball.y >= max_y {
+            ball.y = 2.0 * max_y - ball.y;
+            ball.dy = -ball.dy.abs();
+        }
+
+        // paddles
+        let overlaps = |paddle_y: f32, ball_y: f32| {
+            ball_y + COURT_BALL_SIZE > paddle_y && ball_y < paddle_y + COURT_PADDLE_HEIGHT
+        };
+        let left_face = COURT_PADDLE_FACE_X;
+        let right_face = COURT_WIDTH - COURT_PADDLE_FACE_X - COURT_BALL_SIZE;
+        if ball.dx < 0.0
+            && ball.x <= left_face
+            && ball.x - ball.dx > left_face - COURT_BALL_SIZE
+            && overlaps(self.paddles[0], ball.y)
+        {
+            self.rally += 1;
+            self.state_dirty = true;
+            ball.x = left_face;
+            ball.dx = BALL_SPEED_X * self.ramp.factor(self.rally);
+            ball.dy = deflect(ball.y, self.paddles[0], ball.dx, spin(0));
+        } else if ball.dx > 0.0
+            && ball.x >= right_face
+            && ball.x - ball.dx < right_face + COURT_BALL_SIZE
+            && overlaps(self.paddles[1], ball.y)
+        {
+            self.rally += 1;
+            self.state_dirty = true;
+            ball.x = right_face;
+            ball.dx = -BALL_SPEED_X * self.ramp.factor(self.rally);
+            ball.dy = deflect(ball.y, self.paddles[1], ball.dx, spin(1));
+        }
+
+        // goals
+        if ball.x + COURT_BALL_SIZE < 0.0 {
+            self.score_point(1);
+        } else if ball.x > COURT_WIDTH {
+            self.score_point(0);
+        }
+    }
+
+    fn score_point(&mut self, scorer: usize) {
+        self.scores[scorer] += 1;
+        self.rally = 0;
+        // Serve towards the player who conceded
+        self.serve_dir = if scorer == 0 { 1.0 } else { -1.0 };
+        self.center_ball();
+        self.serve_timer = SERVE_DELAY_TICKS;
+        if self.scores[scorer] >= WINNING_SCORE {
+            self.status = GameStatus::Ended;
+            self.ready = [false, false];
+        }
+        self.state_dirty = true;
+    }
+
+    pub fn ball_msg(&self) -> BallMsg {
+        BallMsg {
+            timestamp: now_ms(),
+            ..self.ball.clone()
+        }
+    }
+
+    pub fn state_msg(&self) -> StateMsg {
+        StateMsg {
+            p1_score: self.scores[0],
+            p2_score: self.scores[1],
+            p1_name: self.names[0].clone(),
+            p2_name: self.names[1].clone(),
+            status: self.status.clone(),
+            rally: self.rally,
+            ramp: Some(self.ramp),
+            timestamp: now_ms(),
+        }
+    }
+
+    /// Returns the pending state message (if any) and clears the dirty flag.
+    pub fn take_state(&mut self) -> Option<StateMsg> {
+        if !self.state_dirty {
+            return None;
+        }
+        self.state_dirty = false;
+        self.ticks_since_state = 0;
+        Some(self.state_msg())
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle_ticks >= MATCH_IDLE_TICKS
+    }
+}
+
+/// Answer to a slot claim
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    Granted,
+    /// Another client holds the slot; `free` is the other slot if nobody does
+    Taken { free: Option<u8> },
+}
+
+// ---------------------------------------------------------------------------
+// Inputs forwarded from the MQTT thread to the tick loop
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+enum ServerInput {
+    /// Slot, session tag, profile name, requested speed ramp, connection number
+    Join(u8, Option<String>, Option<String>, Option<SpeedRamp>, u32),
+    /// Session tag, connection number
+    Leave(String, Option<u32>),
+    Paddle(u8, f32),
+    Serve,
+    Restart,
+    Ready(u8),
+}
+
+fn parse_input(topic: &str, payload: &[u8]) -> Option<(String, ServerInput)> {
+    let game_id = Topics::game_id_of(topic)?;
+    let topics = Topics::new(game_id);
+
+    let input = if topic == topics.join() {
+        let join = serde_json::from_slice::<JoinMsg>(payload).ok()?;
+        let connection = join.connection.unwrap_or(0);
+        ServerInput::Join(join.player, join.client, join.name, join.ramp, connection)
+    } else if topic == topics.leave() {
+        let leave = serde_json::from_slice::<LeaveMsg>(payload).ok()?;
+        ServerInput::Leave(leave.client, leave.connection)
+    } else if topic == topics.p1_paddle() {
+        ServerInput::Paddle(1, serde_json::from_slice::<PaddleMsg>(payload).ok()?.y)
+    } else if topic == topics.p2_paddle() {
+        ServerInput::Paddle(2, serde_json::from_slice::<PaddleMsg>(payload).ok()?.y)
+    } else if topic == topics.serve() {
+        ServerInput::Serve
+    } else if topic == topics.restart() {
+        ServerInput::Restart
+    } else if topic == topics.ready() {
+        ServerInput::Ready(serde_json::from_slice::<ReadyMsg>(payload).ok()?.player)
+    } else {
+        return None;
+    };
+
+    Some((game_id.to_string(), input))
+}
+
+/// Reply for a client ping received at `received` (server clock): the pong
+/// topic to publish on and its payload. Answered straight from the MQTT
+/// thread so the tick loop doesn't add up to a tick of delay.
+fn pong_reply(topic: &str, payload: &[u8], received: u64) -> Option<(String, PongMsg)> {
+    let topics = Topics::new(Topics::game_id_of(topic)?);
+    // pong/game/{id}/{who}/ping
+    let who = topic
+        .strip_prefix(&format!("pong/game/{}/", topics.game_id))?
+        .strip_suffix("/ping")
+        .filter(|who| !who.is_empty() && !who.contains('/'))?;
+    let ping = serde_json::from_slice::<PingMsg>(payload).ok()?;
+    let reply = PongMsg {
+        seq: ping.seq,
+        ping_timestamp: ping.timestamp,
+        received,
+        timestamp: now_ms(),
+    };
+    Some((topics.pong(who), reply))
+}
+
+// ---------------------------------------------------------------------------
+// Config
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub broker_host: String,
+    pub broker_port: u16,
+    pub transport: Transport,
+    pub ws_path: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub tls: TlsSettings,
+    /// Paddle movement bends returns (see `sim::deflect`)
+    pub spin: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        let net = NetworkConfig::default();
+        Self {
+            broker_host: net.broker_host,
+            broker_port: net.broker_port,
+            transport: net.transport,
+            ws_path: net.ws_path,
+            username: net.username,
+            password: net.password,
+            tls: net.tls,
+            spin: false,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Server main loop (blocks forever)
+// ---------------------------------------------------------------------------
+
+/// Returns only if the TLS files can't be loaded.
+pub fn run(config: ServerConfig) -> io::Result<()> {
+    let (input_tx, input_rx) = mpsc::channel::<(String, ServerInput)>();
+
+    let client_id = format!("rust-pong-server-{}", std::process::id());
+    let mut mqttoptions = network::mqtt_options(
+        client_id,
+        &config.broker_host,
+        config.broker_port,
+        config.transport,
+        &config.ws_path,
+        &config.tls,
+    )?;
+    mqttoptions.set_keep_alive(Duration::from_secs(5));
+    if let (Some(user), Some(pass)) = (config.username, config.password) {
+        mqttoptions.set_credentials(user, pass);
+    }
+
+    let (client, mut connection) = Client::new(mqttoptions, 256);
+
+    // Listen on every game at once: "pong/game/+/join" etc.
+    let any = Topics::new("+");
+    let subscriptions = [
+        any.join(),
+        any.leave(),
+        any.p1_paddle(),
+        any.p2_paddle(),
+        any.serve(),
+        any.restart(),
+        any.ready(),
+        any.ping("+"),
+    ];
+
+    // MQTT thread: (re)subscribe on every ConnAck and forward parsed inputs
+    let sub_client = client.clone();
+    thread::spawn(move || {
+        for notification in connection.iter() {
+            match notification {
+                Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                    for topic in &subscriptions {
+                        sub_client.subscribe(topic, QoS::AtMostOnce).ok();
+                    }
+                }
+                Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(msg))) => {
+                    if let Some((topic, pong)) = pong_reply(&msg.topic, &msg.payload, now_ms()) {
+                        if let Ok(payload) = serde_json::to_vec(&pong) {
+                            sub_client.try_publish(topic, QoS::AtMostOnce, false, payload).ok();
+                        }
+                    } else if let Some(input) = parse_input(&msg.topic, &msg.payload) {
+                        if input_tx.send(input).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("pong-server: connection error: {e}, retrying");
+                    thread::sleep(Duration::from_secs(1));
+                }
+                _ => {}
+            }
+        }
+    });
+
+    let spin = config.spin;
+    let mut matches: HashMap<String, Match> = HashMap::new();
+    let tick = Duration::from_micros(1_000_000 / SERVER_TICK_HZ);
+
+    loop {
+        let tick_start = Instant::now();
+
+        while let Ok((game_id, input)) = input_rx.try_recv() {
+            let topics = Topics::new(&game_id);
+            let m = matches.entry(game_id).or_insert_with(|| Match {
+                spin,
+                ..Match::new()
+            });
+            match input {
+                ServerInput::Join(player, session, name, ramp, connection) => {
+                    let claim =
+                        m.on_join(player, session.as_deref(), name.as_deref(), ramp, connection);
+                    if let Some(session) = session {
+                        let reply = ClaimMsg {
+                            player,
+                            granted: claim == Claim::Granted,
+                            free: match claim {
+                                Claim::Granted => None,
+                                Claim::Taken { free } => free,
+                            },
+                            timestamp: now_ms(),
+                        };
+                        if let Ok(payload) = serde_json::to_vec(&reply) {
+                            client
+                                .publish(topics.claim(&session), QoS::AtLeastOnce, false, payload)
+                                .ok();
+                        }
+                    }
+                }
+                ServerInput::Leave(session, connection) => {
+                    // Also covers clients that vanished without clearing their lobby announcement
+                    if let Some(player) = m.on_leave(&session, connection) {
+                        client
+                            .publish(topics.lobby(player), QoS::AtLeastOnce, true, Vec::new())
+                            .ok();
+                    }
+                }
+                ServerInput::Paddle(player, y) => m.on_paddle(player, y),
+                ServerInput::Serve => m.on_serve(),
+                ServerInput::Restart => m.on_restart(),
+                ServerInput::Ready(player) => m.on_ready(player),
+            }
+        }
+
+        matches.retain(|_, m| !m.is_idle());
+
+        for (game_id, m) in matches.iter_mut() {
+            m.tick();
+            let topics = Topics::new(game_id);
+
+            if m.status == GameStatus::Playing {
+                if let Ok(payload) = serde_json::to_vec(&m.ball_msg()) {
+                    client.publish(topics.ball(), QoS::AtMostOnce, false, payload).ok();
+                }
+            }
+            if let Some(state) = m.take_state() {
+                if let Ok(payload) = serde_json::to_vec(&state) {
+                    client.publish(topics.state(), QoS::AtMostOnce, false, payload).ok();
+                }
+            }
+        }
+
+        let elapsed = tick_start.elapsed();
+        if elapsed < tick {
+            thread::sleep(tick - elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT_FACE: f32 = COURT_PADDLE_FACE_X;
+
+    /// Both slots joined, ball resting for the serve
+    fn playing() -> Match {
+        let mut m = Match::new();
+        m.on_join(1, None, None, None, 0);
+        m.on_join(2, None, None, None, 0);
+        m
+    }
+
+    /// Put the ball in play at `position` with `velocity` (per tick)
+    fn in_play(mut m: Match, position: [f32; 2], velocity: [f32; 2]) -> Match {
+        m.serve_timer = 0;
+        m.ball.x = position[0];
+        m.ball.y = position[1];
+        m.ball.dx = velocity[0];
+        m.ball.dy = velocity[1];
+        m
+    }
+
+    #[test]
+    fn second_join_starts_play_with_the_ball_resting() {
+        let mut m = Match::new();
+        m.on_join(1, None, None, None, 0);
+        assert_eq!(m.status, GameStatus::Waiting);
+        m.on_join(2, None, None, None, 0);
+        assert_eq!(m.status, GameStatus::Playing);
+        assert_eq!((m.ball.dx, m.ball.dy), (0.0, 0.0));
+        assert_eq!(m.serve_timer, SERVE_DELAY_TICKS);
+    }
+
+    #[test]
+    fn serve_launches_the_ball() {
+        let mut m = playing();
+        m.serve_dir = -1.0;
+        m.on_serve();
+        assert_eq!(m.serve_timer, 0);
+        assert_eq!(m.ball.dx, -BALL_SPEED_X);
+        assert_eq!(m.ball.dy.abs(), BALL_SPEED_Y);
+    }
+
+    #[test]
+    fn ball_launches_itself_after_the_serve_delay() {
+        let mut m = playing();
+        for _ in 0..SERVE_DELAY_TICKS - 1 {
+            m.tick();
+        }
+        assert_eq!(m.ball.dx, 0.0);
+        m.tick();
+        assert_eq!(m.ball.dx.abs(), BALL_SPEED_X);
+    }
+
+    #[test]
+    fn serve_is_ignored_while_waiting_or_in_a_rally() {
+        let mut m = Match::new();
+        m.on_serve();
+        assert_eq!(m.ball.dx, 0.0);
+
+        let mut m = in_play(playing(), [20.0, 5.0], [BALL_SPEED_X, BALL_SPEED_Y]);
+        m.on_serve();
+        assert_eq!((m.ball.dx, m.ball.dy), (BALL_SPEED_X, BALL_SPEED_Y));
+    }
+
+    #[test]
+    fn ball_bounces_off_the_walls() {
+        let mut m = in_play(playing(), [20.0, 0.1], [BALL_SPEED_X, -BALL_SPEED_Y]);
+        m.tick();
+        assert!(m.ball.y >= 0.0);
+        assert_eq!(m.ball.dy, BALL_SPEED_Y);
+
+        let bottom = COURT_HEIGHT - COURT_BALL_SIZE;
+        let mut m = in_play(
+            playing(),
+            [20.0, bottom - 0.1],
+            [BALL_SPEED_X, BALL_SPEED_Y],
+        );
+        m.tick();
+        assert!(m.ball.y <= bottom);
+        assert_eq!(m.ball.dy, -BALL_SPEED_Y);
+    }
+
+    #[test]
+    fn paddle_returns_the_ball() {
+        let mut m = playing();
+        m.paddles[0] = 4.0;
+        let mut m = in_play(m, [LEFT_FACE + 0.2, 5.0], [-BALL_SPEED_X, 0.0]);
+        m.tick();
+        assert_eq!(m.ball.x, LEFT_FACE);
+        assert!(m.ball.dx > 0.0);
+        assert_eq!(m.rally, 1);
+        assert_eq!(m.scores, [0, 0]);
+    }
+
+    #[test]
+    fn a_miss_scores_for_the_other_side() {
+        let mut m = playing();
+        m.paddles[0] = 9.0;
+        let mut m = in_play(m, [LEFT_FACE + 0.2, 1.0], [-BALL_SPEED_X, 0.0]);
+        for _ in 0..10 {
+            if m.scores != [0, 0] {
+                break;
+            }
+            m.tick();
+        }
+        assert_eq!(m.scores, [0, 1]);
+        assert_eq!(m.rally, 0);
+        // Ball back at centre, serving towards the player who conceded
+        assert_eq!((m.ball.dx, m.ball.dy), (0.0, 0.0));
+        assert_eq!(m.serve_dir, -1.0);
+        assert_eq!(m.serve_timer, SERVE_DELAY_TICKS);
+        assert_eq!(m.status, GameStatus::Playing);
+    }
+
+    #[test]
+    fn winning_point_ends_the_game() {
+        let mut m = playing();
+        m.scores = [WINNING_SCORE - 1, 0];
+        m.paddles[1] = 0.0;
+        let mut m = in_play(m, [COURT_WIDTH - 0.2, 9.0], [BALL_SPEED_X, 0.0]);
+        m.tick();
+        assert_eq!(m.scores, [WINNING_SCORE, 0]);
+        assert_eq!(m.status, GameStatus::Ended);
+        // Nothing moves once it's over
+        let ball = m.ball.clone();
+        m.tick();
+        assert_eq!((m.ball.x, m.ball.y), (ball.x, ball.y));
+    }
+
+    #[test]
+    fn both_players_ready_starts_a_new_game() {
+        let mut m = playing();
+        m.scores = [WINNING_SCORE, 2];
+        m.status = GameStatus::Ended;
+
+        m.on_ready(1);
+        m.on_ready(1);
+        assert_eq!(m.status, GameStatus::Ended);
+        m.on_ready(2);
+        assert_eq!(m.status, GameStatus::Playing);
+        assert_eq!(m.scores, [0, 0]);
+        assert_eq!(m.ready, [false, false]);
+    }
+
+    #[test]
+    fn first_claim_on_a_slot_wins() {
+        let mut m = Match::new();
+        assert_eq!(m.on_join(1, Some("alice"), None, None, 0), Claim::Granted);
+        assert_eq!(
+            m.on_join(1, Some("bob"), None, None, 0),
+            Claim::Taken { free: Some(2) }
+        );
+        assert_eq!(m.on_join(2, Some("bob"), None, None, 0), Claim::Granted);
+        assert_eq!(
+            m.on_join(1, Some("carol"), None, None, 0),
+            Claim::Taken { free: None }
+        );
+        assert_eq!(m.status, GameStatus::Playing);
+    }
+
+    #[test]
+    fn owner_can_rejoin_its_slot() {
+        let mut m = Match::new();
+        m.on_join(1, Some("alice"), Some("Alice"), None, 0);
+        m.on_join(2, Some("bob"), None, None, 0);
+        assert_eq!(
+            m.on_join(1, Some("alice"), Some("Alice"), None, 0),
+            Claim::Granted
+        );
+        assert_eq!(m.owners[0].as_deref(), Some("alice"));
+        assert_eq!(m.status, GameStatus::Playing);
+    }
+
+    #[test]
+    fn leaving_frees_the_slot() {
+        let mut m = Match::new();
+        m.on_join(1, Some("alice"), Some("Alice"), None, 0);
+        assert_eq!(m.on_leave("bob", None), None);
+        assert_eq!(m.on_leave("alice", None), Some(1));
+        assert_eq!(m.names[0], None);
+        assert!(!m.joined[0]);
+        assert_eq!(m.on_join(1, Some("bob"), None, None, 0), Claim::Granted);
+    }
+
+    #[test]
+    fn a_late_will_from_a_replaced_connection_is_ignored() {
+        let mut m = Match::new();
+        m.on_join(1, Some("alice"), None, None, 0);
+        m.on_join(2, Some("bob"), None, None, 0);
+        // alice reconnects; her first connection's will arrives after the new join
+        assert_eq!(m.on_join(1, Some("alice"), None, None, 1), Claim::Granted);
+        assert_eq!(m.on_leave("alice", Some(0)), None);
+        assert_eq!(m.owners[0].as_deref(), Some("alice"));
+        assert!(m.joined[0]);
+        // A retried join from the old connection doesn't lower the bar again
+        m.on_join(1, Some("alice"), None, None, 0);
+        assert_eq!(m.on_leave("alice", Some(0)), None);
+        // The current connection's will, or a leave without a number, still frees the slot
+        assert_eq!(m.on_leave("alice", Some(1)), Some(1));
+        assert_eq!(m.on_leave("bob", None), Some(2));
+    }
+
+    #[test]
+    fn untagged_clients_only_get_unowned_slots() {
+        let mut m = Match::new();
+        m.on_join(1, Some("alice"), None, None, 0);
+        assert_eq!(
+            m.on_join(1, None, None, None, 0),
+            Claim::Taken { free: Some(2) }
+        );
+        assert_eq!(m.on_join(2, None, None, None, 0), Claim::Granted);
+        // An untagged player owns nothing, so a tagged one may take over the slot
+        assert_eq!(m.on_join(2, Some("bob"), None, None, 0), Claim::Granted);
+    }
+
+    #[test]
+    fn ready_is_ignored_unless_the_game_is_over() {
+        let mut m = playing();
+        m.on_ready(1);
+        m.on_ready(2);
+        assert_eq!(m.ready, [false, false]);
+
+        m.status = GameStatus::Ended;
+        m.on_ready(3);
+        assert_eq!(m.ready, [false, false]);
+    }
+}
@@ -0,0 +1,980 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::game::{
+    GameType, COURT_BALL_SIZE, COURT_HEIGHT, COURT_PADDLE_FACE_X, COURT_PADDLE_HEIGHT, COURT_WIDTH,
+    PLAYER_NAME_CHAR_LEN,
+};
+use crate::helpers::string_to_char_array;
+
+// ---------------------------------------------------------------------------
+// Local physics (Local Versus, Play vs CPU, screensaver)
+//
+// Everything the local modes simulate lives here, free of the terminal: time
+// only moves when `tick` is called with the elapsed time, and randomness comes
+// from a seeded RNG. `Game` drives it from the frame loop with the wall clock
+// and a random seed; tests drive it with fixed steps and fixed seeds.
+//
+// Positions are in court units, the same 40x12 space the server uses for
+// network games, so the terminal size only matters when drawing.
+// ---------------------------------------------------------------------------
+
+/// Court units per tick
+pub const DEFAULT_BALL_VELOCITY_X: f32 = 1.2;
+pub const DEFAULT_BALL_VELOCITY_Y: f32 = 0.4;
+/// Paddle travel per key press
+pub const PADDLE_STEP: f32 = 0.5;
+/// Lowest paddle top
+pub const PADDLE_MAX_Y: f32 = COURT_HEIGHT - COURT_PADDLE_HEIGHT;
+pub const STARTING_POWER_MOVES: u8 = 10;
+/// Horizontal speed of a power shot
+const POWER_VELOCITY_X: f32 = 2.4;
+/// How far in front of the paddle a power shot can be fired, at difficulty
+/// 1.0 and 0.0 (it keeps shrinking above 1.0)
+const POWER_RANGE_MIN: f32 = 1.6;
+const POWER_RANGE_MAX: f32 = 4.8;
+/// A paddle is drawn highlighted this long after a power shot
+const POWER_FLASH_MS: u64 = 200;
+
+// ---------------------------------------------------------------------------
+// Paddle returns (the server's physics uses these too)
+//
+// Where the ball meets the paddle sets the return angle: dead centre comes
+// back flat, the ends come back steep. With spin on, a paddle that is moving
+// as it strikes bends the ball further its own way. Each return in a rally
+// is a little faster than the last, up to the ramp's cap.
+// ---------------------------------------------------------------------------
+
+/// Return slope (|dy| / |dx|) off the very end of a paddle
+pub const HIT_DEFLECTION: f32 = 0.75;
+/// Slope added by spin for a paddle moving one court height per second
+pub const SPIN_TRANSFER: f32 = 0.5;
+/// Steepest return, spin included
+pub const MAX_DEFLECTION: f32 = 1.0;
+/// How much of each new speed sample `PaddleMotion` takes in
+const MOTION_SMOOTHING: f32 = 0.3;
+/// Upper bound for any `SpeedRamp` cap
+const MAX_SPEED_FACTOR: f32 = 3.0;
+
+/// How the ball speeds up over a rally, as multiples of the serve speed.
+/// Local games use the `[game.ramp]` settings; online, whoever starts a game
+/// sets it and the server announces it in its state message.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeedRamp {
+    /// Added per paddle return (0 = no ramp)
+    pub step: f32,
+    /// Fastest the ball gets
+    pub max: f32,
+}
+
+impl Default for SpeedRamp {
+    fn default() -> Self {
+        Self {
+            step: 0.05,
+            max: 1.6,
+        }
+    }
+}
+
+impl SpeedRamp {
+    /// Speed multiplier after `rally` returns
+    pub fn factor(&self, rally: u32) -> f32 {
+        (1.0 + self.step * rally as f32).min(self.max)
+    }
+
+    /// Step and cap pulled into range (0 - 1 and 1 - 3)
+    pub fn sanitized(self) -> Self {
+        let clean = |v: f32, lo: f32, hi: f32| if v.is_finite() { v.clamp(lo, hi) } else { lo };
+        Self {
+            step: clean(self.step, 0.0, 1.0),
+            max: clean(self.max, 1.0, MAX_SPEED_FACTOR),
+        }
+    }
+}
+
+/// Vertical speed for a ball leaving a paddle at horizontal speed `dx`, given
+/// both their tops. `spin` is the paddle's speed (court units per second) when
+/// spin is on.
+pub fn deflect(ball_y: f32, paddle_y: f32, dx: f32, spin: Option<f32>) -> f32 {
+    let reach = (COURT_PADDLE_HEIGHT + COURT_BALL_SIZE) / 2.0;
+    let offset =
+        ((ball_y + COURT_BALL_SIZE / 2.0) - (paddle_y + COURT_PADDLE_HEIGHT / 2.0)) / reach;
+    let mut slope = offset.clamp(-1.0, 1.0) * HIT_DEFLECTION;
+    if let Some(speed) = spin {
+        slope += speed / COURT_HEIGHT * SPIN_TRANSFER;
+    }
+    slope.clamp(-MAX_DEFLECTION, MAX_DEFLECTION) * dx.abs()
+}
+
+/// A paddle's vertical speed, smoothed over a few ticks so that movement
+/// arriving in bursts (key repeat, network packets) still registers
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PaddleMotion {
+    last_y: Option<f32>,
+    speed: f32,
+}
+
+impl PaddleMotion {
+    /// Call once per tick with the paddle's top and the time since the last call
+    pub fn update(&mut self, y: f32, dt: f32) {
+        if let Some(last_y) = self.last_y {
+            if dt > 0.0 {
+                self.speed += ((y - last_y) / dt - self.speed) * MOTION_SMOOTHING;
+            }
+        }
+        self.last_y = Some(y);
+    }
+
+    /// Court units per second, positive = down
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Local match
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy)]
+pub struct ComputerAI {
+    reaction_delay: f32,     // Time before reacting to ball direction change
+    last_ball_direction: i8, // Track ball direction changes
+    reaction_timer: f32,     // Current reaction delay timer
+    prediction_error: f32,   // How far off the prediction can be
+    max_speed: f32,          // Maximum movement speed
+    current_speed: f32,      // Current movement speed (with acceleration)
+    target_position: f32,    // Where the AI wants to move
+    // difficulty: f32,         // 0.0 to 1.0, affects all parameters
+    fatigue: f32, // Increases over time, affects performance
+}
+
+impl ComputerAI {
+    /// `difficulty` 0.0 (easy) - 2.0 (hard)
+    pub fn new(difficulty: f32) -> Self {
+        Self {
+            reaction_delay: 0.2 + (2.0 - difficulty) * 0.5, // 0.2-0.7 seconds
+            last_ball_direction: 0,
+            reaction_timer: 0.0,
+            prediction_error: 0.8 + (1.0 - difficulty) * 1.0, // up to 1.8 units error
+            max_speed: 0.32 + difficulty * 0.34,              // 0.32-1.0 units per tick
+            current_speed: 0.0,
+            target_position: 0.0,
+            fatigue: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Player {
+    pub name: [char; PLAYER_NAME_CHAR_LEN],
+    pub score: u32,
+
+    pub power_moves_left: u8,
+    /// Simulation clock (ms) of the last power shot
+    pub last_power_at: Option<u64>,
+
+    /// Paddle top in court units (0 .. PADDLE_MAX_Y)
+    pub y: f32,
+
+    pub is_computer: bool,
+    computer_ai: Option<ComputerAI>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Ball {
+    /// Top-left corner, court units
+    pub x: f32,
+    pub y: f32,
+    /// Court units per tick
+    pub dx: f32,
+    pub dy: f32,
+    pub is_powered: bool,
+}
+
+impl Ball {
+    fn centered() -> Self {
+        Self {
+            x: (COURT_WIDTH - COURT_BALL_SIZE) / 2.0,
+            y: (COURT_HEIGHT - COURT_BALL_SIZE) / 2.0,
+            dx: DEFAULT_BALL_VELOCITY_X,
+            dy: DEFAULT_BALL_VELOCITY_Y,
+            is_powered: false,
+        }
+    }
+}
+
+/// What happened during one `tick`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TickEvents {
+    /// Index of the player whose paddle returned the ball
+    pub hit: Option<usize>,
+    /// Index of the player who scored
+    pub scored: Option<usize>,
+    /// The ball bounced off the top or bottom wall
+    pub wall: bool,
+}
+
+#[derive(Debug)]
+pub struct Sim {
+    pub players: [Player; 2],
+    pub ball: Ball,
+    /// 0.0 - 2.0; widens or narrows the power shot window
+    pub difficulty: f32,
+    /// Paddle movement bends returns (see `deflect`)
+    pub spin: bool,
+    motion: [PaddleMotion; 2],
+    pub ramp: SpeedRamp,
+    /// Paddle returns since the last serve
+    pub rally: u32,
+    /// CPU vs CPU demo: steadier AIs that tire more slowly
+    demo: bool,
+    clock_ms: u64,
+    rng: StdRng,
+}
+
+/// Whether a ball with its top at `ball_y` is level with a paddle whose top is at `paddle_y`
+fn overlaps(paddle_y: f32, ball_y: f32) -> bool {
+    ball_y + COURT_BALL_SIZE > paddle_y && ball_y < paddle_y + COURT_PADDLE_HEIGHT
+}
+
+impl Sim {
+    /// A new match. The same `seed` replays the same match for the same inputs.
+    pub fn new(names: [&str; 2], game_type: &GameType, difficulty: f32, seed: u64) -> Self {
+        let difficulty = difficulty.clamp(0.0, 2.0);
+        let demo = *game_type == GameType::ScreenSaver;
+        let player = |name: &str, is_computer: bool, has_ai: bool| Player {
+            name: string_to_char_array(name),
+            y: PADDLE_MAX_Y / 2.0,
+            is_computer,
+            computer_ai: has_ai.then(|| ComputerAI::new(difficulty)),
+            power_moves_left: STARTING_POWER_MOVES,
+            last_power_at: None,
+            score: 0,
+        };
+        let vs_computer = *game_type == GameType::VsComputer;
+        Self {
+            players: [
+                player(names[0], false, demo),
+                player(names[1], vs_computer, demo || vs_computer),
+            ],
+            ball: Ball::centered(),
+            difficulty,
+            spin: false,
+            motion: [PaddleMotion::default(); 2],
+            ramp: SpeedRamp::default(),
+            rally: 0,
+            demo,
+            clock_ms: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Simulation clock, ms since the start
+    pub fn now_ms(&self) -> u64 {
+        self.clock_ms
+    }
+
+    /// True for a short moment after the player's last power shot
+    pub fn power_flash(&self, player_index: usize) -> bool {
+        self.players[player_index]
+            .last_power_at
+            .is_some_and(|t| self.clock_ms.saturating_sub(t) < POWER_FLASH_MS)
+    }
+
+    /// Reset scores, power moves and the ball for a rematch
+    pub fn restart(&mut self) {
+        for player in self.players.iter_mut() {
+            player.score = 0;
+            player.power_moves_left = STARTING_POWER_MOVES;
+            player.last_power_at = None;
+        }
+        self.ball = Ball::centered();
+        self.rally = 0;
+    }
+
+    /// Advance the match by one step, `dt` after the previous one: the computer
+    /// paddles move (in random order), then the ball.
+    pub fn tick(&mut self, dt: Duration) -> TickEvents {
+        self.clock_ms += dt.as_millis() as u64;
+        let dt = dt.as_secs_f32();
+        if self.rng.random() {
+            self.update_computer_player(0, dt);
+            self.update_computer_player(1, dt);
+        } else {
+            self.update_computer_player(1, dt);
+            self.update_computer_player(0, dt);
+        }
+        for (motion, player) in self.motion.iter_mut().zip(&self.players) {
+            motion.update(player.y, dt);
+        }
+        self.update_ball_position()
+    }
+
+    /// Move a human's paddle one step: direction > 0 is up, < 0 down.
+    pub fn move_player(&mut self, player_index: usize, direction: i8) {
+        let player = &mut self.players[player_index];
+
+        if direction == 0 || player.is_computer {
+            return;
+        }
+
+        if direction > 0 {
+            // up
+            player.y = (player.y - PADDLE_STEP).max(0.0);
+        } else {
+            // down
+            player.y = (player.y + PADDLE_STEP).min(PADDLE_MAX_Y);
+        }
+    }
+
+    fn update_ball_position(&mut self) -> TickEvents {
+        let mut events = TickEvents::default();
+        let players = &self.players;
+        let ball = &mut self.ball;
+        let spin = |idx: usize| self.spin.then(|| self.motion[idx].speed());
+
+        ball.x += ball.dx;
+        ball.y += ball.dy;
+
+        // collision with top and bottom walls
+        let max_y = COURT_HEIGHT - COURT_BALL_SIZE;
+        if ball.y <= 0.0 {
+            ball.y = -ball.y;
+            ball.dy = ball.dy.abs();
+            events.wall = true;
+        } else if ball.y >= max_y {
+            ball.y = 2.0 * max_y - ball.y;
+            ball.dy = -ball.dy.abs();
+            events.wall = true;
+        }
+
+        // Paddles only return a ball that is crossing their face this tick;
+        // once it is past, nothing saves it.
+        let left_face = COURT_PADDLE_FACE_X;
+        let right_face = COURT_WIDTH - COURT_PADDLE_FACE_X - COURT_BALL_SIZE;
+
+        // ball collision with Player 1's bar (left side)
+        if ball.dx < 0.0
+            && ball.x <= left_face
+            && ball.x - ball.dx > left_face - COURT_BALL_SIZE
+            && overlaps(players[0].y, ball.y)
+        {
+            self.rally += 1;
+            ball.dx = DEFAULT_BALL_VELOCITY_X * self.ramp.factor(self.rally);
+            ball.dy = deflect(ball.y, players[0].y, ball.dx, spin(0));
+            ball.x = left_face;
+            ball.is_powered = false;
+            events.hit = Some(0);
+            return events;
+        }
+
+        // ball collision with Player 2's bar (right side)
+        if ball.dx > 0.0
+            && ball.x >= right_face
+            && ball.x - ball.dx < right_face + COURT_BALL_SIZE
+            && overlaps(players[1].y, ball.y)
+        {
+            self.rally += 1;
+            ball.dx = -DEFAULT_BALL_VELOCITY_X * self.ramp.factor(self.rally);
+            ball.dy = deflect(ball.y, players[1].y, ball.dx, spin(1));
+            ball.x = right_face;
+            ball.is_powered = false;
+            events.hit = Some(1);
+            return events;
+        }
+
+        // ball went off the court: left → P2 scores, right → P1 scores
+        let scorer = if ball.x + COURT_BALL_SIZE < 0.0 {
+            1
+        } else if ball.x > COURT_WIDTH {
+            0
+        } else {
+            return events;
+        };
+        self.players[scorer].score += 1;
+        self.rally = 0;
+        events.scored = Some(scorer);
+
+        // serve again from the middle, at a random height and in a random direction
+        ball.x = (COURT_WIDTH - COURT_BALL_SIZE) / 2.0;
+        ball.y = self
+            .rng
+            .random_range(1.0..COURT_HEIGHT - COURT_BALL_SIZE - 1.0);
+        let direction = if self.rng.random() { 1.0 } else { -1.0 };
+        ball.dx = direction * DEFAULT_BALL_VELOCITY_X;
+        ball.dy = DEFAULT_BALL_VELOCITY_Y.copysign(ball.dy);
+        ball.is_powered = false;
+
+        events
+    }
+
+    fn update_computer_player(&mut self, player_index: usize, dt: f32) {
+        let computer = &mut self.players[player_index];
+        let ball = &self.ball;
+        let rng = &mut self.rng;
+
+        let Some(ai) = computer.computer_ai.as_mut() else {
+            return;
+        };
+
+        let paddle_x = if player_index == 0 {
+            COURT_PADDLE_FACE_X // Player 1's paddle is on the left
+        } else {
+            COURT_WIDTH - COURT_PADDLE_FACE_X - COURT_BALL_SIZE // Player 2's paddle is on the right
+        };
+
+        // increase fatigue over time
+        if self.demo {
+            ai.fatigue = (ai.fatigue + dt * 0.001).min(0.05); // much less fatigue for AI vs AI
+        } else {
+            ai.fatigue = (ai.fatigue + dt * 0.009).min(0.3);
+        }
+
+        // now, let's calculate the direction the ball is moving towards
+        let ball_direction_x = ball.dx.signum() as i8; // -1 or 1
+
+        // if ball direction changed, add a reaction timer
+        if ball_direction_x != ai.last_ball_direction && ball.dx != 0.0 {
+            ai.last_ball_direction = ball_direction_x;
+            ai.reaction_timer = ai.reaction_delay + ai.fatigue * 0.5;
+        }
+
+        ai.reaction_timer = (ai.reaction_timer - dt).max(0.0);
+
+        // check if ball is coming towards computer paddle
+        let is_ball_coming = if player_index == 0 {
+            ball.dx < 0.0 // Player 1's paddle is on the left
+        } else {
+            ball.dx > 0.0 // Player 2's paddle is on the right
+        };
+
+        let half_paddle = COURT_PADDLE_HEIGHT / 2.0;
+        let paddle_center = computer.y + half_paddle;
+
+        if !is_ball_coming || ai.reaction_timer > 0.0 {
+            // neutral positioning
+            // slowly drift towards the center
+
+            let center_y = COURT_HEIGHT / 2.0;
+            ai.target_position = paddle_center + (center_y - paddle_center) * 0.1;
+        } else {
+            // active/predictive positioning
+            // "predict" ball position with wall bounces
+
+            let time_to_paddle_x = (paddle_x - ball.x) / ball.dx;
+            let mut pred_y = ball.y + COURT_BALL_SIZE / 2.0 + ball.dy * time_to_paddle_x;
+
+            // simulate top and bottom wall bounces
+            while !(0.0..=COURT_HEIGHT).contains(&pred_y) {
+                if pred_y < 0.0 {
+                    pred_y = -pred_y; // bounce off top wall
+                } else {
+                    pred_y = 2.0 * COURT_HEIGHT - pred_y; // bounce off bottom wall
+                }
+            }
+
+            // sprinkle some prediction errors -,-
+            let (error_magnitude, oops_chance, random_chance) = if self.demo {
+                (ai.prediction_error * 0.3, 0.01, 0.02) // much less error
+            } else {
+                (
+                    ai.prediction_error * (1.0 + ai.fatigue),
+                    0.05 + ai.fatigue * 0.1,
+                    0.1,
+                )
+            };
+            let prediction_error = (rng.random::<f32>() - 0.5) * error_magnitude;
+            pred_y += prediction_error;
+
+            // make big oopsies occasionally
+            if rng.random::<f32>() < oops_chance {
+                pred_y += (rng.random::<f32>() - 0.5) * 1.2;
+            }
+
+            // add some final randomness
+            if rng.random::<f32>() < random_chance {
+                pred_y += (rng.random::<f32>() - 0.5) * 0.4;
+            }
+
+            // clamp to fix
+            pred_y = pred_y.clamp(half_paddle, COURT_HEIGHT - half_paddle);
+
+            ai.target_position = pred_y;
+        }
+
+        // smooth movement with acceleration
+        let distance_to_target = ai.target_position - paddle_center;
+        let desired_speed = distance_to_target.abs().min(ai.max_speed);
+        let acceleration = 0.8;
+        if distance_to_target.abs() > 0.2 {
+            ai.current_speed = (ai.current_speed + acceleration * dt).min(desired_speed);
+        } else {
+            ai.current_speed = (ai.current_speed - acceleration * dt * 2.0).max(0.0);
+        }
+
+        // add some jitter and behavioral quirks
+        let jitter = if self.demo {
+            (rng.random::<f32>() - 0.5) * 0.008 * (1.0 + ai.fatigue)
+        } else {
+            0.0
+        };
+        let movement = distance_to_target.signum() * ai.current_speed + jitter;
+
+        // apply new position with clamping
+        computer.y = (paddle_center + movement - half_paddle).clamp(0.0, PADDLE_MAX_Y);
+    }
+
+    /// Fire a power shot if the ball is coming at the player's paddle and close
+    /// enough (a wider window on easier difficulties). Returns whether it fired.
+    pub fn power_move(&mut self, player_index: usize) -> bool {
+        let player = &mut self.players[player_index];
+
+        if player.power_moves_left == 0 {
+            return false; // no power move left
+        }
+
+        let ball = &mut self.ball;
+
+        let is_ball_approaching = if player_index == 0 {
+            ball.dx < 0.0
+        } else {
+            ball.dx > 0.0
+        };
+
+        let within_bar = overlaps(player.y, ball.y);
+
+        let allowed_range =
+            (POWER_RANGE_MAX - POWER_RANGE_MIN) * (1.0 - self.difficulty) + POWER_RANGE_MIN;
+
+        // how far the ball still is from the paddle face
+        let distance = if player_index == 0 {
+            ball.x - COURT_PADDLE_FACE_X
+        } else {
+            COURT_WIDTH - COURT_PADDLE_FACE_X - COURT_BALL_SIZE - ball.x
+        };
+        let within_x = distance > -COURT_BALL_SIZE && distance < allowed_range;
+
+        if is_ball_approaching && within_bar && within_x {
+            // power move: send ball flying in the correct direction
+            let speed = POWER_VELOCITY_X * self.ramp.factor(self.rally);
+            ball.dx = if player_index == 0 { speed } else { -speed };
+            ball.is_powered = true;
+            player.power_moves_left -= 1;
+            player.last_power_at = Some(self.clock_ms);
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ball's top can range over 0..11; paddle faces are at x = 1.5 and 37.5.
+    const STEP: Duration = Duration::from_millis(25);
+    const RIGHT_FACE: f32 = COURT_WIDTH - COURT_PADDLE_FACE_X - COURT_BALL_SIZE;
+
+    fn sim(game_type: GameType) -> Sim {
+        Sim::new(["left", "right"], &game_type, 1.0, 7)
+    }
+
+    fn with_ball(mut sim: Sim, position: [f32; 2], velocity: [f32; 2]) -> Sim {
+        sim.ball = Ball {
+            x: position[0],
+            y: position[1],
+            dx: velocity[0],
+            dy: velocity[1],
+            is_powered: false,
+        };
+        sim
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn ball_bounces_off_the_top_wall() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [20.0, 0.25], [1.2, -0.4]);
+        let events = s.tick(STEP);
+        assert!(events.wall);
+        assert_near(s.ball.x, 21.2);
+        assert_near(s.ball.y, 0.15);
+        assert_near(s.ball.dy, 0.4);
+    }
+
+    #[test]
+    fn ball_bounces_off_the_bottom_wall() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [20.0, 10.75], [1.2, 0.4]);
+        let events = s.tick(STEP);
+        assert!(events.wall);
+        assert_near(s.ball.y, 10.85);
+        assert_near(s.ball.dy, -0.4);
+    }
+
+    #[test]
+    fn ball_flies_straight_between_walls() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [20.0, 5.0], [1.2, 0.4]);
+        assert_eq!(s.tick(STEP), TickEvents::default());
+        assert_near(s.ball.x, 21.2);
+        assert_near(s.ball.y, 5.4);
+    }
+
+    #[test]
+    fn left_paddle_returns_the_ball() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.0, 5.0], [-1.2, 0.4]);
+        s.players[0].y = 4.0;
+        let events = s.tick(STEP);
+        assert_eq!(events.hit, Some(0));
+        assert_eq!(s.ball.x, COURT_PADDLE_FACE_X);
+        assert_near(s.ball.dx, DEFAULT_BALL_VELOCITY_X * s.ramp.factor(1));
+        assert_eq!(s.rally, 1);
+    }
+
+    #[test]
+    fn left_paddle_ends_a_power_shot_too() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [3.0, 5.0], [-2.4, 0.0]);
+        s.ball.is_powered = true;
+        s.players[0].y = 4.0;
+        assert_eq!(s.tick(STEP).hit, Some(0));
+        assert!(!s.ball.is_powered);
+        assert_near(s.ball.dx, DEFAULT_BALL_VELOCITY_X * s.ramp.factor(1));
+    }
+
+    #[test]
+    fn right_paddle_returns_the_ball_and_ends_a_power_shot() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [36.0, 5.0], [2.4, 0.0]);
+        s.ball.is_powered = true;
+        s.players[1].y = 4.0;
+        let events = s.tick(STEP);
+        assert_eq!(events.hit, Some(1));
+        assert_eq!(s.ball.x, RIGHT_FACE);
+        assert_near(s.ball.dx, -DEFAULT_BALL_VELOCITY_X * s.ramp.factor(1));
+        assert!(!s.ball.is_powered);
+    }
+
+    #[test]
+    fn paddle_catches_the_ball_by_its_corner() {
+        // Ball spans y 6.5..7.5, paddle 4..7
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.0, 6.5], [-1.2, 0.0]);
+        s.players[0].y = 4.0;
+        assert_eq!(s.tick(STEP).hit, Some(0));
+
+        // Ball spans y 7..8: just below
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.0, 7.0], [-1.2, 0.0]);
+        s.players[0].y = 4.0;
+        assert_eq!(s.tick(STEP).hit, None);
+    }
+
+    #[test]
+    fn return_angle_follows_where_the_ball_meets_the_paddle() {
+        // Paddle 4..7, centre 5.5; the ball is 1 tall
+        assert_eq!(deflect(5.0, 4.0, 1.0, None), 0.0);
+        assert_near(deflect(7.0, 4.0, -1.0, None), HIT_DEFLECTION);
+        assert_near(deflect(3.0, 4.0, 1.0, None), -HIT_DEFLECTION);
+        // halfway to the end, at twice the speed
+        assert_near(deflect(6.0, 4.0, 2.0, None), 0.5 * HIT_DEFLECTION * 2.0);
+        // Off the end counts as the end
+        assert_near(deflect(10.0, 4.0, 1.0, None), HIT_DEFLECTION);
+    }
+
+    #[test]
+    fn spin_bends_the_return_the_paddle_s_way() {
+        assert_near(deflect(5.0, 4.0, 1.0, Some(COURT_HEIGHT)), SPIN_TRANSFER);
+        assert_near(deflect(5.0, 4.0, 1.0, Some(-COURT_HEIGHT)), -SPIN_TRANSFER);
+        // capped
+        assert_near(deflect(7.0, 4.0, 1.0, Some(60.0)), MAX_DEFLECTION);
+    }
+
+    #[test]
+    fn paddle_motion_is_smoothed() {
+        let mut motion = PaddleMotion::default();
+        motion.update(4.0, 0.1);
+        assert_eq!(motion.speed(), 0.0);
+        motion.update(5.0, 0.1);
+        assert_near(motion.speed(), 10.0 * MOTION_SMOOTHING);
+        for _ in 0..50 {
+            motion.update(5.0, 0.1);
+        }
+        assert!(motion.speed().abs() < 0.01);
+    }
+
+    #[test]
+    fn off_centre_hits_come_back_angled() {
+        // Ball (after the step) spans 6..7 on a 4..7 paddle: low half
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.0, 6.0], [-1.2, 0.0]);
+        s.players[0].y = 4.0;
+        assert_eq!(s.tick(STEP).hit, Some(0));
+        assert_near(s.ball.dy, deflect(6.0, 4.0, s.ball.dx, None));
+        assert!(s.ball.dy > 0.0);
+    }
+
+    #[test]
+    fn moving_paddle_adds_spin_only_when_enabled() {
+        let run = |spin: bool| {
+            let mut s = with_ball(sim(GameType::LocalVersus), [8.0, 5.0], [-1.2, 0.0]);
+            s.spin = spin;
+            s.players[0].y = 2.0;
+            // paddle heading down, 0.5 per tick, as the ball arrives
+            loop {
+                s.move_player(0, -1);
+                if s.tick(STEP).hit.is_some() {
+                    break;
+                }
+            }
+            s.ball.dy
+        };
+        let flat = run(false);
+        let spun = run(true);
+        assert!(spun > flat, "{spun} <= {flat}");
+    }
+
+    #[test]
+    fn ramp_adds_a_step_per_return_up_to_the_cap() {
+        let ramp = SpeedRamp {
+            step: 0.1,
+            max: 1.3,
+        };
+        assert_eq!(ramp.factor(0), 1.0);
+        assert_near(ramp.factor(2), 1.2);
+        assert_eq!(ramp.factor(3), 1.3);
+        assert_eq!(ramp.factor(50), 1.3);
+
+        let flat = SpeedRamp {
+            step: 0.0,
+            max: 2.0,
+        };
+        assert_eq!(flat.factor(50), 1.0);
+    }
+
+    #[test]
+    fn ramp_is_kept_in_range() {
+        let wild = SpeedRamp {
+            step: -1.0,
+            max: 10.0,
+        };
+        assert_eq!(
+            wild.sanitized(),
+            SpeedRamp {
+                step: 0.0,
+                max: MAX_SPEED_FACTOR
+            }
+        );
+        let broken = SpeedRamp {
+            step: f32::NAN,
+            max: 0.5,
+        };
+        assert_eq!(
+            broken.sanitized(),
+            SpeedRamp {
+                step: 0.0,
+                max: 1.0
+            }
+        );
+    }
+
+    #[test]
+    fn rally_speeds_the_ball_up_and_a_point_resets_it() {
+        let mut s = sim(GameType::LocalVersus);
+        s.ramp = SpeedRamp {
+            step: 0.1,
+            max: 1.3,
+        };
+        s.players[0].y = 4.0;
+        s.players[1].y = 4.0;
+        for n in 1..=5 {
+            // Put the ball just in front of whichever paddle it's heading for
+            let x = if s.ball.dx < 0.0 { 2.5 } else { 36.5 };
+            s.ball = Ball {
+                x,
+                y: 5.0,
+                dy: 0.0,
+                ..s.ball.clone()
+            };
+            assert!(s.tick(STEP).hit.is_some());
+            assert_eq!(s.rally, n);
+            assert_near(s.ball.dx.abs(), DEFAULT_BALL_VELOCITY_X * s.ramp.factor(n));
+        }
+        assert_near(s.ball.dx.abs(), DEFAULT_BALL_VELOCITY_X * 1.3);
+
+        // Next one gets past
+        s.players[0].y = 0.0;
+        s.players[1].y = 0.0;
+        s.ball.y = 8.0;
+        while s.tick(STEP).scored.is_none() {}
+        assert_eq!(s.rally, 0);
+        assert_eq!(s.ball.dx.abs(), DEFAULT_BALL_VELOCITY_X);
+    }
+
+    #[test]
+    fn power_shots_ride_the_ramp() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.5, 5.0], [-1.2, 0.0]);
+        s.players[0].y = 4.0;
+        s.rally = 4;
+        assert!(s.power_move(0));
+        assert_near(s.ball.dx, POWER_VELOCITY_X * s.ramp.factor(4));
+    }
+
+    #[test]
+    fn ball_past_the_left_paddle_scores_for_the_right() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.0, 5.0], [-1.2, 0.0]);
+        s.players[0].y = 0.0;
+
+        // Past the paddle: still on the court, no point yet
+        assert_eq!(s.tick(STEP), TickEvents::default());
+        assert_eq!(s.tick(STEP), TickEvents::default());
+        assert_near(s.ball.x, -0.4);
+
+        // Too late to save it now
+        s.players[0].y = 4.0;
+        let events = s.tick(STEP);
+        assert_eq!(events.scored, Some(1));
+        assert_eq!(events.hit, None);
+        assert_eq!((s.players[0].score, s.players[1].score), (0, 1));
+
+        // Re-served from the middle
+        assert_eq!(s.ball.x, (COURT_WIDTH - COURT_BALL_SIZE) / 2.0);
+        assert!((1.0..COURT_HEIGHT - 2.0).contains(&s.ball.y));
+        assert_eq!(s.ball.dx.abs(), DEFAULT_BALL_VELOCITY_X);
+    }
+
+    #[test]
+    fn ball_past_the_right_paddle_scores_for_the_left() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [37.0, 5.0], [1.2, 0.0]);
+        s.players[1].y = 0.0;
+        s.tick(STEP);
+        s.tick(STEP);
+        let events = s.tick(STEP);
+        assert_eq!(events.scored, Some(0));
+        assert_eq!((s.players[0].score, s.players[1].score), (1, 0));
+    }
+
+    #[test]
+    fn power_shot_needs_the_ball_coming_close_and_level() {
+        // Difficulty 1.0: up to 1.6 units in front of the paddle
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.5, 5.0], [-1.2, 0.0]);
+        s.players[0].y = 4.0;
+
+        assert!(s.power_move(0));
+        assert_eq!(s.ball.dx, POWER_VELOCITY_X);
+        assert!(s.ball.is_powered);
+        assert_eq!(s.players[0].power_moves_left, STARTING_POWER_MOVES - 1);
+        assert!(s.power_flash(0));
+
+        // Now moving away
+        assert!(!s.power_move(0));
+        assert_eq!(s.players[0].power_moves_left, STARTING_POWER_MOVES - 1);
+
+        // Too far out
+        s.ball = Ball {
+            x: 4.0,
+            y: 5.0,
+            dx: -1.2,
+            dy: 0.0,
+            is_powered: false,
+        };
+        assert!(!s.power_move(0));
+
+        // Not level with the paddle
+        s.ball.x = 2.5;
+        s.ball.y = 1.0;
+        assert!(!s.power_move(0));
+    }
+
+    #[test]
+    fn power_shot_from_the_right() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [36.5, 5.0], [1.2, 0.0]);
+        s.players[1].y = 4.0;
+        assert!(s.power_move(1));
+        assert_eq!(s.ball.dx, -POWER_VELOCITY_X);
+    }
+
+    #[test]
+    fn easier_difficulty_widens_the_power_window() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [5.0, 5.0], [-1.2, 0.0]);
+        s.players[0].y = 4.0;
+        assert!(!s.power_move(0));
+        s.difficulty = 0.0;
+        assert!(s.power_move(0));
+    }
+
+    #[test]
+    fn power_shots_run_out() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.5, 5.0], [-1.2, 0.0]);
+        s.players[0].y = 4.0;
+        s.players[0].power_moves_left = 0;
+        assert!(!s.power_move(0));
+        assert_eq!(s.ball.dx, -1.2);
+    }
+
+    #[test]
+    fn power_flash_follows_the_sim_clock() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.5, 5.0], [-1.2, 0.0]);
+        s.players[0].y = 4.0;
+        assert!(s.power_move(0));
+        s.tick(Duration::from_millis(150));
+        assert!(s.power_flash(0));
+        s.tick(Duration::from_millis(100));
+        assert!(!s.power_flash(0));
+        assert_eq!(s.now_ms(), 250);
+    }
+
+    #[test]
+    fn paddles_stay_on_the_court() {
+        let mut s = sim(GameType::LocalVersus);
+        for _ in 0..50 {
+            s.move_player(0, 1);
+            s.move_player(1, -1);
+        }
+        assert_eq!(s.players[0].y, 0.0);
+        assert_eq!(s.players[1].y, PADDLE_MAX_Y);
+    }
+
+    #[test]
+    fn keys_do_not_move_the_computer() {
+        let mut s = sim(GameType::VsComputer);
+        let before = s.players[1].y;
+        s.move_player(1, 1);
+        assert_eq!(s.players[1].y, before);
+    }
+
+    #[test]
+    fn restart_resets_scores_power_and_ball() {
+        let mut s = with_ball(sim(GameType::LocalVersus), [2.5, 5.0], [-1.2, 0.0]);
+        s.players[0].y = 4.0;
+        s.power_move(0);
+        s.players[1].score = 4;
+        s.restart();
+        assert_eq!((s.players[0].score, s.players[1].score), (0, 0));
+        assert_eq!(s.players[0].power_moves_left, STARTING_POWER_MOVES);
+        assert!(!s.power_flash(0));
+        assert_eq!(s.ball, Ball::centered());
+    }
+
+    /// Everything after the seed is deterministic: two screensaver matches with
+    /// the same seed and steps stay identical, computer paddles and serves included.
+    #[test]
+    fn same_seed_same_match() {
+        let run = |seed| {
+            let mut s = Sim::new(["a", "b"], &GameType::ScreenSaver, 1.0, seed);
+            let mut points = 0;
+            for _ in 0..2_000 {
+                if s.tick(STEP).scored.is_some() {
+                    points += 1;
+                }
+            }
+            (
+                s.ball.clone(),
+                [s.players[0].y, s.players[1].y],
+                [s.players[0].score, s.players[1].score],
+                points,
+            )
+        };
+        let first = run(42);
+        assert_eq!(first, run(42));
+        assert_eq!(first.3, first.2.iter().sum::<u32>());
+    }
+}
@@ -0,0 +1,663 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::game::{COURT_HEIGHT, COURT_WIDTH};
+use crate::network::{BallMsg, PaddleMsg};
+use crate::server::SERVER_TICK_HZ;
+
+// ---------------------------------------------------------------------------
+// Client-side smoothing of remote state (network games)
+//
+// Ball packets arrive at the server tick rate at best, late or bunched up at
+// worst; the opponent's paddle arrives whenever they move it. Instead of
+// snapping to each packet, the client estimates each sender's clock from the
+// packet timestamps and draws the packets on one local timeline:
+//
+//   ball = off            - draw the latest packet as-is
+//   ball = extrapolate    - dead-reckon from the latest packet's velocity; when a
+//                           new packet disagrees, blend the error out instead of jumping
+//   ball = interpolate    - draw `delay_ms` in the past, between two real packets
+//   paddle = off          - draw the latest opponent paddle packet
+//   paddle = interpolate  - draw the opponent paddle `delay_ms` in the past
+//
+// With both set to interpolate, ball and paddle are drawn at the same
+// estimated moment, so the ball meets the paddle where the server saw it.
+//
+// All times are milliseconds, passed in by the caller so tests can replay
+// synthetic packet streams.
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BallSmoothing {
+    Off,
+    Extrapolate,
+    #[default]
+    Interpolate,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaddleSmoothing {
+    Off,
+    #[default]
+    Interpolate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmoothingSettings {
+    pub ball: BallSmoothing,
+    /// Opponent paddle
+    pub paddle: PaddleSmoothing,
+    /// How far in the past "interpolate" draws, in ms (shared by ball and
+    /// paddle so both are drawn at the same moment)
+    pub delay_ms: u64,
+}
+
+impl Default for SmoothingSettings {
+    fn default() -> Self {
+        Self {
+            ball: BallSmoothing::Interpolate,
+            paddle: PaddleSmoothing::Interpolate,
+            delay_ms: 100,
+        }
+    }
+}
+
+/// Never dead-reckon further than this past the newest packet (the server
+/// stops sending while a point is being set up, and paddles can't be predicted).
+const MAX_EXTRAPOLATION_MS: f64 = 250.0;
+/// Time over which a prediction error is blended out after a packet arrives
+const CORRECTION_MS: f64 = 100.0;
+/// Errors (or gaps between packets) bigger than this are a reset, not jitter: snap
+const SNAP_DISTANCE: f32 = 4.0;
+/// How quickly the clock offset follows packets that arrive later than the best seen
+const OFFSET_DRIFT: f64 = 0.01;
+const MAX_SAMPLES: usize = 32;
+/// A timestamp this far from the newest sample means the sender's clock changed
+/// (server restart, a different opponent) or it went quiet: start over
+const RESTART_GAP_MS: f64 = 5_000.0;
+/// Ball y runs 0..=BALL_MAX_Y (the server bounces a 1-unit ball off both walls)
+const BALL_MAX_Y: f32 = COURT_HEIGHT - 1.0;
+/// Paddle moves this large between two packets are drawn as a jump, not a sweep
+const PADDLE_SNAP_DISTANCE: f32 = COURT_HEIGHT / 2.0;
+
+// ---------------------------------------------------------------------------
+// Server clock estimate
+// ---------------------------------------------------------------------------
+
+/// Maps local time onto the server's clock using one-way packet timestamps.
+/// The least-delayed packet gives the best offset, so the estimate follows the
+/// minimum of (arrival - sent), drifting up slowly if every packet gets later.
+/// "Server time" here therefore means the newest server state we could have.
+#[derive(Debug, Clone, Default)]
+pub struct ServerClock {
+    offset_ms: Option<f64>,
+}
+
+impl ServerClock {
+    /// Record a packet the server stamped `server_ms` that arrived at `local_ms`.
+    pub fn observe(&mut self, server_ms: u64, local_ms: u64) {
+        let sample = local_ms as f64 - server_ms as f64;
+        self.offset_ms = Some(match self.offset_ms {
+            Some(offset) if sample >= offset => offset + (sample - offset) * OFFSET_DRIFT,
+            _ => sample,
+        });
+    }
+
+    pub fn server_time(&self, local_ms: u64) -> Option<f64> {
+        self.offset_ms.map(|offset| local_ms as f64 - offset)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sample buffer
+// ---------------------------------------------------------------------------
+
+enum Pushed {
+    Added,
+    /// Not newer than the newest sample; ignored
+    Stale,
+    /// Far from the newest sample: older samples were discarded
+    Restarted,
+}
+
+/// Where a render time falls in a `Timeline`
+enum Span<T> {
+    /// Before the oldest sample
+    Before(T),
+    /// Between two samples, `k` of the way from the first to the second
+    Between(T, T, f32),
+    /// At or past the newest sample, `dt` ms after it
+    After(T, f64),
+}
+
+/// Timestamped samples, oldest first, strictly increasing in time.
+#[derive(Debug, Clone)]
+struct Timeline<T> {
+    samples: VecDeque<(f64, T)>,
+}
+
+impl<T: Copy> Timeline<T> {
+    fn new() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(MAX_SAMPLES),
+        }
+    }
+
+    fn push(&mut self, t: f64, sample: T) -> Pushed {
+        let pushed = match self.samples.back() {
+            Some(&(newest, _)) if (t - newest).abs() > RESTART_GAP_MS => {
+                self.samples.clear();
+                Pushed::Restarted
+            }
+            Some(&(newest, _)) if t <= newest => return Pushed::Stale,
+            _ => Pushed::Added,
+        };
+        self.samples.push_back((t, sample));
+        while self.samples.len() > MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        pushed
+    }
+
+    fn newest(&self) -> Option<(f64, T)> {
+        self.samples.back().copied()
+    }
+
+    fn span(&self, t: f64) -> Option<Span<T>> {
+        let (newest_t, newest) = self.newest()?;
+        if t >= newest_t {
+            return Some(Span::After(newest, t - newest_t));
+        }
+        let after = self.samples.iter().position(|&(st, _)| st > t)?;
+        let (bt, b) = self.samples[after];
+        Some(match after.checked_sub(1).map(|i| self.samples[i]) {
+            Some((at, a)) => Span::Between(a, b, ((t - at) / (bt - at)) as f32),
+            None => Span::Before(b),
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Ball smoother
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy)]
+struct BallSample {
+    x: f32,
+    y: f32,
+    /// Court units per server tick
+    dx: f32,
+    dy: f32,
+}
+
+impl BallSample {
+    /// Where this sample's ball would be `dt_ms` later, bouncing off the walls.
+    fn advance(&self, dt_ms: f64) -> (f32, f32) {
+        let ticks = (dt_ms * SERVER_TICK_HZ as f64 / 1000.0) as f32;
+        let x = (self.x + self.dx * ticks).clamp(0.0, COURT_WIDTH);
+        let y = reflect(self.y + self.dy * ticks, BALL_MAX_Y);
+        (x, y)
+    }
+}
+
+/// Fold `v` back into 0..=max as if it bounced off both ends.
+fn reflect(v: f32, max: f32) -> f32 {
+    let m = v.rem_euclid(2.0 * max);
+    if m > max {
+        2.0 * max - m
+    } else {
+        m
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+#[derive(Debug, Clone)]
+pub struct BallSmoother {
+    mode: BallSmoothing,
+    delay_ms: f64,
+    clock: ServerClock,
+    samples: Timeline<BallSample>,
+    /// Extrapolate: prediction error left over from the last packet, and the
+    /// local time it was measured; fades to zero over CORRECTION_MS
+    correction: (f32, f32),
+    correction_at: u64,
+    last_drawn: Option<(f32, f32)>,
+}
+
+impl BallSmoother {
+    pub fn new(settings: &SmoothingSettings) -> Self {
+        Self {
+            mode: settings.ball,
+            delay_ms: settings.delay_ms as f64,
+            clock: ServerClock::default(),
+            samples: Timeline::new(),
+            correction: (0.0, 0.0),
+            correction_at: 0,
+            last_drawn: None,
+        }
+    }
+
+    pub fn clock(&self) -> &ServerClock {
+        &self.clock
+    }
+
+    /// Feed a ball packet received at `local_ms`. Returns false if it was dropped as
+    /// stale (out of order).
+    pub fn on_packet(&mut self, msg: &BallMsg, local_ms: u64) -> bool {
+        let sample = BallSample {
+            x: msg.x,
+            y: msg.y,
+            dx: msg.dx,
+            dy: msg.dy,
+        };
+        match self.samples.push(msg.timestamp as f64, sample) {
+            Pushed::Stale => return false,
+            Pushed::Restarted => self.clock = ServerClock::default(),
+            Pushed::Added => {}
+        }
+        self.clock.observe(msg.timestamp, local_ms);
+
+        // Reconcile: keep drawing where we were and fade the difference out
+        if self.mode == BallSmoothing::Extrapolate {
+            self.correction = (0.0, 0.0);
+            if let (Some(drawn), Some(predicted)) = (self.last_drawn, self.extrapolate(local_ms)) {
+                if distance(drawn, predicted) < SNAP_DISTANCE {
+                    self.correction = (drawn.0 - predicted.0, drawn.1 - predicted.1);
+                    self.correction_at = local_ms;
+                }
+            }
+        }
+        true
+    }
+
+    /// Position to draw at `local_ms`, in court units. None until the first packet.
+    pub fn position(&mut self, local_ms: u64) -> Option<(f32, f32)> {
+        let position = match self.mode {
+            BallSmoothing::Off => self.samples.newest().map(|(_, s)| (s.x, s.y)),
+            BallSmoothing::Extrapolate => self.extrapolate(local_ms).map(|(x, y)| {
+                let elapsed = local_ms.saturating_sub(self.correction_at) as f64;
+                let fade = (1.0 - elapsed / CORRECTION_MS).max(0.0) as f32;
+                (x + self.correction.0 * fade, y + self.correction.1 * fade)
+            }),
+            BallSmoothing::Interpolate => self.interpolate(local_ms),
+        };
+        self.last_drawn = position;
+        position
+    }
+
+    fn extrapolate(&self, local_ms: u64) -> Option<(f32, f32)> {
+        let (newest_t, newest) = self.samples.newest()?;
+        let now = self.clock.server_time(local_ms)?;
+        Some(newest.advance((now - newest_t).clamp(0.0, MAX_EXTRAPOLATION_MS)))
+    }
+
+    fn interpolate(&self, local_ms: u64) -> Option<(f32, f32)> {
+        let t = self.clock.server_time(local_ms)? - self.delay_ms;
+        Some(match self.samples.span(t)? {
+            Span::Before(b) => (b.x, b.y),
+            Span::Between(a, b, _) if distance((a.x, a.y), (b.x, b.y)) > SNAP_DISTANCE => (a.x, a.y),
+            Span::Between(a, b, k) => (a.x + (b.x - a.x) * k, a.y + (b.y - a.y) * k),
+            // Buffer ran dry: fall back to a short extrapolation
+            Span::After(newest, dt) => newest.advance(dt.min(MAX_EXTRAPOLATION_MS)),
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Opponent paddle smoother
+// ---------------------------------------------------------------------------
+
+/// Paddle packets are stamped by the opponent's clock, so they get their own
+/// `ServerClock`; drawing both streams `delay_ms` before local now keeps them in step.
+#[derive(Debug, Clone)]
+pub struct PaddleSmoother {
+    mode: PaddleSmoothing,
+    delay_ms: f64,
+    clock: ServerClock,
+    samples: Timeline<f32>,
+}
+
+impl PaddleSmoother {
+    pub fn new(settings: &SmoothingSettings) -> Self {
+        Self {
+            mode: settings.paddle,
+            delay_ms: settings.delay_ms as f64,
+            clock: ServerClock::default(),
+            samples: Timeline::new(),
+        }
+    }
+
+    /// Feed a paddle packet received at `local_ms`. Returns false if it was dropped as
+    /// stale (out of order).
+    pub fn on_packet(&mut self, msg: &PaddleMsg, local_ms: u64) -> bool {
+        match self.samples.push(msg.timestamp as f64, msg.y) {
+            Pushed::Stale => return false,
+            Pushed::Restarted => self.clock = ServerClock::default(),
+            Pushed::Added => {}
+        }
+        self.clock.observe(msg.timestamp, local_ms);
+        true
+    }
+
+    /// Paddle top to draw at `local_ms`, in court units. None until the first packet.
+    pub fn position(&self, local_ms: u64) -> Option<f32> {
+        if self.mode == PaddleSmoothing::Off {
+            return self.samples.newest().map(|(_, y)| y);
+        }
+        let t = self.clock.server_time(local_ms)? - self.delay_ms;
+        Some(match self.samples.span(t)? {
+            Span::Before(y) => y,
+            Span::Between(a, b, _) if (b - a).abs() > PADDLE_SNAP_DISTANCE => a,
+            Span::Between(a, b, k) => a + (b - a) * k,
+            // Paddles stop dead, so never guess past the newest packet
+            Span::After(y, _) => y,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Server tick length in ms (the server moves the ball dx/dy per tick)
+    const TICK_MS: f64 = 1000.0 / SERVER_TICK_HZ as f64;
+
+    fn settings(ball: BallSmoothing) -> SmoothingSettings {
+        SmoothingSettings {
+            ball,
+            ..SmoothingSettings::default()
+        }
+    }
+
+    /// Server clock runs an hour ahead of ours; packets take 30ms.
+    const SKEW: u64 = 3_600_000;
+    const LATENCY: u64 = 30;
+
+    /// A ball moving right at 0.3 units/tick along y = 5, `t` ms into the
+    /// rally, stamped with the (skewed) server clock.
+    fn straight(t: u64) -> BallMsg {
+        let ticks = (t as f64 / TICK_MS) as f32;
+        BallMsg {
+            x: 2.0 + 0.3 * ticks,
+            y: 5.0,
+            dx: 0.3,
+            dy: 0.0,
+            timestamp: SKEW + t,
+        }
+    }
+
+    /// Deliver the packet sent at rally time `t` after the usual latency.
+    fn feed(smoother: &mut BallSmoother, t: u64) {
+        smoother.on_packet(&straight(t), t + LATENCY);
+    }
+
+    #[test]
+    fn off_draws_latest_packet() {
+        let mut s = BallSmoother::new(&settings(BallSmoothing::Off));
+        assert_eq!(s.position(0), None);
+        feed(&mut s, 0);
+        feed(&mut s, 50);
+        let expected = straight(50);
+        assert_eq!(s.position(200), Some((expected.x, expected.y)));
+    }
+
+    #[test]
+    fn extrapolate_moves_between_packets() {
+        let mut s = BallSmoother::new(&settings(BallSmoothing::Extrapolate));
+        feed(&mut s, 0);
+        s.position(LATENCY);
+        // 25ms after the packet arrived the ball should be where the server has it by then
+        let (x, y) = s.position(25 + LATENCY).unwrap();
+        assert!((x - straight(25).x).abs() < 0.01, "x = {x}");
+        assert_eq!(y, 5.0);
+    }
+
+    #[test]
+    fn extrapolate_reflects_off_walls() {
+        let mut s = BallSmoother::new(&settings(BallSmoothing::Extrapolate));
+        let msg = BallMsg {
+            x: 20.0,
+            y: 10.0,
+            dx: 0.0,
+            dy: 0.5,
+            timestamp: 1_000,
+        };
+        s.on_packet(&msg, 1_000);
+        // 4 ticks later: 10 + 2 = 12 -> bounced back off the bottom wall to 10
+        let (_, y) = s.position(1_000 + (4.0 * TICK_MS) as u64 + 1).unwrap();
+        assert!((y - (2.0 * BALL_MAX_Y - 12.0)).abs() < 0.05, "y = {y}");
+    }
+
+    #[test]
+    fn extrapolate_stops_when_packets_stop() {
+        let mut s = BallSmoother::new(&settings(BallSmoothing::Extrapolate));
+        feed(&mut s, 0);
+        let capped = s.position(LATENCY + MAX_EXTRAPOLATION_MS as u64).unwrap();
+        assert_eq!(s.position(LATENCY + 5_000), Some(capped));
+    }
+
+    #[test]
+    fn extrapolate_blends_out_prediction_error() {
+        let mut s = BallSmoother::new(&settings(BallSmoothing::Extrapolate));
+        feed(&mut s, 0);
+        let before = s.position(LATENCY + 50).unwrap();
+
+        // The next packet has the ball 0.6 units lower than predicted
+        let mut msg = straight(50);
+        msg.y += 0.6;
+        s.on_packet(&msg, 50 + LATENCY);
+
+        // No visible jump on arrival...
+        let at_arrival = s.position(50 + LATENCY).unwrap();
+        assert!(distance(before, at_arrival) < 0.01);
+        // ...and on the server's line once the correction has faded
+        let settled = s.position(50 + LATENCY + CORRECTION_MS as u64).unwrap();
+        assert!((settled.1 - 5.6).abs() < 0.01, "y = {}", settled.1);
+    }
+
+    #[test]
+    fn extrapolate_snaps_on_reset() {
+        let mut s = BallSmoother::new(&settings(BallSmoothing::Extrapolate));
+        feed(&mut s, 0);
+        s.position(LATENCY + 40);
+        let center = BallMsg {
+            x: COURT_WIDTH / 2.0,
+            y: COURT_HEIGHT / 2.0,
+            dx: 0.0,
+            dy: 0.0,
+            timestamp: SKEW + 50,
+        };
+        s.on_packet(&center, 50 + LATENCY);
+        assert_eq!(
+            s.position(50 + LATENCY),
+            Some((COURT_WIDTH / 2.0, COURT_HEIGHT / 2.0))
+        );
+    }
+
+    #[test]
+    fn interpolate_draws_in_the_past_between_packets() {
+        let mut s = BallSmoother::new(&settings(BallSmoothing::Interpolate));
+        for t in (0..=300).step_by(50) {
+            feed(&mut s, t);
+        }
+        // At local 300+latency the estimated server time is 300; draw at 200..
+        let (x, _) = s.position(300 + LATENCY).unwrap();
+        assert!((x - straight(200).x).abs() < 0.01, "x = {x}");
+        // ...and halfway between the 200 and 250 packets 25ms later
+        let (x, _) = s.position(325 + LATENCY).unwrap();
+        let mid = (straight(200).x + straight(250).x) / 2.0;
+        assert!((x - mid).abs() < 0.01, "x = {x}");
+    }
+
+    #[test]
+    fn interpolate_steps_across_resets() {
+        let mut s = BallSmoother::new(&settings(BallSmoothing::Interpolate));
+        feed(&mut s, 0);
+        let center = BallMsg {
+            x: COURT_WIDTH / 2.0,
+            y: COURT_HEIGHT / 2.0,
+            dx: 0.0,
+            dy: 0.0,
+            timestamp: SKEW + 50,
+        };
+        s.on_packet(&center, 50 + LATENCY);
+        s.on_packet(&BallMsg { timestamp: SKEW + 100, ..center }, 100 + LATENCY);
+        // Halfway between the old ball and the center: no sweep across the court
+        let start = straight(0);
+        assert_eq!(s.position(125 + LATENCY), Some((start.x, start.y)));
+    }
+
+    #[test]
+    fn jittery_stream_never_moves_backwards() {
+        // Packets every 16ms, delivered with 0-60ms of jitter (some bunched, some reordered)
+        let jitter = [0, 45, 10, 60, 5, 30, 0, 55, 20, 15, 40, 0, 35, 50, 25, 5];
+        for mode in [BallSmoothing::Extrapolate, BallSmoothing::Interpolate] {
+            let mut s = BallSmoother::new(&settings(mode));
+            let mut arrivals: Vec<(u64, u64)> = (0..jitter.len() as u64 * 4)
+                .map(|i| {
+                    let sent = i * 16;
+                    (sent + LATENCY + jitter[i as usize % jitter.len()], sent)
+                })
+                .collect();
+            arrivals.sort();
+
+            let mut next = 0;
+            let mut last_x = f32::MIN;
+            for now in (0..arrivals.last().unwrap().0).step_by(4) {
+                while next < arrivals.len() && arrivals[next].0 <= now {
+                    feed(&mut s, arrivals[next].1);
+                    next += 1;
+                }
+                if let Some((x, _)) = s.position(now) {
+                    assert!(x >= last_x - 0.05, "{mode:?}: x went back {last_x} -> {x} at {now}");
+                    last_x = last_x.max(x);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stale_packets_are_ignored() {
+        let mut s = BallSmoother::new(&settings(BallSmoothing::Off));
+        feed(&mut s, 100);
+        feed(&mut s, 50);
+        let expected = straight(100);
+        assert_eq!(s.position(500), Some((expected.x, expected.y)));
+    }
+
+    #[test]
+    fn clock_tracks_least_delayed_packet() {
+        let mut clock = ServerClock::default();
+        clock.observe(10_000, 80);
+        clock.observe(10_050, 100); // 30ms faster than the first
+        assert_eq!(clock.server_time(100), Some(10_050.0));
+        clock.observe(10_100, 190); // late packet barely moves the estimate
+        assert!(clock.server_time(100).unwrap() > 10_049.0);
+    }
+
+    /// The opponent's clock is two hours behind ours, a different skew from the server's.
+    const OPPONENT_SKEW: u64 = 7_200_000;
+
+    /// Opponent paddle at `y`, sent at rally time `t`
+    fn paddle(t: u64, y: f32) -> PaddleMsg {
+        PaddleMsg {
+            y,
+            timestamp: OPPONENT_SKEW + t,
+        }
+    }
+
+    fn paddle_settings(paddle: PaddleSmoothing) -> SmoothingSettings {
+        SmoothingSettings {
+            paddle,
+            ..SmoothingSettings::default()
+        }
+    }
+
+    #[test]
+    fn paddle_off_draws_latest_packet() {
+        let mut s = PaddleSmoother::new(&paddle_settings(PaddleSmoothing::Off));
+        assert_eq!(s.position(0), None);
+        s.on_packet(&paddle(0, 1.0), LATENCY);
+        s.on_packet(&paddle(40, 6.0), 40 + LATENCY);
+        assert_eq!(s.position(40 + LATENCY), Some(6.0));
+    }
+
+    #[test]
+    fn paddle_interpolates_in_the_past() {
+        let mut s = PaddleSmoother::new(&paddle_settings(PaddleSmoothing::Interpolate));
+        s.on_packet(&paddle(0, 2.0), LATENCY);
+        s.on_packet(&paddle(40, 4.0), 40 + LATENCY);
+        // Nothing older than the first packet: hold it
+        assert_eq!(s.position(40 + LATENCY), Some(2.0));
+        // 100ms delay: 120ms after the first arrival is halfway to the second
+        assert_eq!(s.position(120 + LATENCY), Some(3.0));
+        // Past the newest packet the paddle stays put
+        assert_eq!(s.position(1_000), Some(4.0));
+    }
+
+    #[test]
+    fn paddle_jumps_across_large_moves() {
+        let mut s = PaddleSmoother::new(&paddle_settings(PaddleSmoothing::Interpolate));
+        s.on_packet(&paddle(0, 0.0), LATENCY);
+        s.on_packet(&paddle(40, 9.0), 40 + LATENCY);
+        assert_eq!(s.position(120 + LATENCY), Some(0.0));
+        assert_eq!(s.position(140 + LATENCY), Some(9.0));
+    }
+
+    #[test]
+    fn paddle_ignores_stale_packets() {
+        let mut s = PaddleSmoother::new(&paddle_settings(PaddleSmoothing::Off));
+        s.on_packet(&paddle(100, 3.0), 100 + LATENCY);
+        s.on_packet(&paddle(50, 8.0), 120 + LATENCY);
+        assert_eq!(s.position(500), Some(3.0));
+    }
+
+    #[test]
+    fn paddle_follows_a_new_opponent_clock() {
+        // A replacement opponent whose clock is a minute behind the last one
+        let mut s = PaddleSmoother::new(&paddle_settings(PaddleSmoothing::Off));
+        s.on_packet(&paddle(0, 3.0), LATENCY);
+        let behind = PaddleMsg {
+            y: 7.0,
+            timestamp: OPPONENT_SKEW - 60_000,
+        };
+        assert!(s.on_packet(&behind, 100 + LATENCY));
+        assert_eq!(s.position(100 + LATENCY), Some(7.0));
+    }
+
+    #[test]
+    fn paddle_and_ball_are_drawn_at_the_same_moment() {
+        // The opponent keeps their paddle on the ball's y. With both streams
+        // interpolated, the drawn paddle should sit on the drawn ball even though
+        // the two senders' clocks disagree with each other and with ours.
+        let settings = SmoothingSettings::default();
+        let mut ball = BallSmoother::new(&settings);
+        let mut pad = PaddleSmoother::new(&settings);
+        let ball_y = |t: u64| 1.0 + 0.05 * (t as f64 / TICK_MS) as f32;
+
+        for t in (0..400).step_by(16) {
+            ball.on_packet(
+                &BallMsg {
+                    x: 20.0,
+                    y: ball_y(t),
+                    dx: 0.0,
+                    dy: 0.05,
+                    timestamp: SKEW + t,
+                },
+                t + LATENCY,
+            );
+            pad.on_packet(&paddle(t, ball_y(t)), t + LATENCY);
+        }
+        for now in (150..400).step_by(7) {
+            let (_, by) = ball.position(now).unwrap();
+            let py = pad.position(now).unwrap();
+            assert!((by - py).abs() < 0.01, "ball {by} vs paddle {py} at {now}");
+        }
+    }
+}
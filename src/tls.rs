@@ -0,0 +1,280 @@
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{self, WebPkiSupportedAlgorithms};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// ---------------------------------------------------------------------------
+// TLS / mutual-TLS for the MQTT connection
+//
+//   [broker.tls]
+//   enabled = true
+//   ca_file = "/etc/pong/ca.pem"          # omit to trust the system roots
+//   client_cert = "/etc/pong/client.pem"  # both set = mutual TLS
+//   client_key = "/etc/pong/client.key"
+//   verify_name = "broker.example.com"    # when connecting by IP
+//   insecure_skip_verify = false          # dev only: accept any certificate
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsSettings {
+    pub enabled: bool,
+    /// PEM bundle of CA certificates; the system trust store is used when unset
+    pub ca_file: Option<PathBuf>,
+    /// PEM client certificate chain for mutual TLS
+    pub client_cert: Option<PathBuf>,
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1) matching `client_cert`
+    pub client_key: Option<PathBuf>,
+    /// Name the broker certificate must match instead of the broker host,
+    /// e.g. when the broker is reached by IP address or through a tunnel.
+    /// Verification only: the handshake still sends the broker host as SNI
+    /// (none for an IP address), since rumqttc dials and names the same host.
+    pub verify_name: Option<String>,
+    /// Skip certificate verification entirely. Development brokers only.
+    pub insecure_skip_verify: bool,
+}
+
+/// rustls client configuration for `settings`, used when `settings.enabled`.
+/// Fails if a certificate or key file can't be read or parsed.
+pub fn client_config(settings: &TlsSettings) -> io::Result<ClientConfig> {
+    let verifier: Arc<dyn ServerCertVerifier> = if settings.insecure_skip_verify {
+        Arc::new(AcceptAnyCert(algorithms()))
+    } else {
+        let name = match &settings.verify_name {
+            Some(name) => Some(
+                ServerName::try_from(name.trim().to_string())
+                    .map_err(|_| invalid(format!("invalid TLS verify name '{}'", name)))?,
+            ),
+            None => None,
+        };
+        let roots = root_store(settings.ca_file.as_deref())?;
+        let webpki = WebPkiServerVerifier::builder(Arc::new(roots))
+            .build()
+            .map_err(invalid)?;
+        match name {
+            Some(name) => Arc::new(NameOverride {
+                inner: webpki,
+                name,
+            }),
+            None => webpki,
+        }
+    };
+
+    let builder = ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(verifier);
+
+    match (&settings.client_cert, &settings.client_key) {
+        (Some(cert), Some(key)) => builder
+            .with_client_auth_cert(read_certs(cert)?, read_key(key)?)
+            .map_err(invalid),
+        (None, None) => Ok(builder.with_no_client_auth()),
+        _ => Err(invalid("client_cert and client_key must be set together")),
+    }
+}
+
+fn root_store(ca_file: Option<&Path>) -> io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    let certs = match ca_file {
+        Some(path) => read_certs(path)?,
+        None => rustls_native_certs::load_native_certs()?,
+    };
+    roots.add_parsable_certificates(certs);
+    if roots.is_empty() {
+        return Err(invalid("no usable CA certificates found"));
+    }
+    Ok(roots)
+}
+
+fn read_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(open(path)?);
+    let certs = rustls_pemfile::certs(&mut reader).collect::<io::Result<Vec<_>>>()?;
+    if certs.is_empty() {
+        return Err(invalid(format!("{}: no PEM certificates", path.display())));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(open(path)?);
+    rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| invalid(format!("{}: no PEM private key", path.display())))
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    File::open(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+}
+
+fn invalid(e: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+fn algorithms() -> WebPkiSupportedAlgorithms {
+    crypto::ring::default_provider().signature_verification_algorithms
+}
+
+// ---------------------------------------------------------------------------
+// Certificate verifiers
+// ---------------------------------------------------------------------------
+
+/// Normal chain validation, but against `name` rather than the host we dialled.
+#[derive(Debug)]
+struct NameOverride {
+    inner: Arc<WebPkiServerVerifier>,
+    name: ServerName<'static>,
+}
+
+impl ServerCertVerifier for NameOverride {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.inner
+            .verify_server_cert(end_entity, intermediates, &self.name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// `insecure_skip_verify`: trusts any certificate. Handshake signatures are
+/// still checked so the session keys belong to whoever sent the certificate.
+#[derive(Debug)]
+struct AcceptAnyCert(WebPkiSupportedAlgorithms);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.0)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.0)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `contents` to a per-process temp file for the settings to point at.
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("pong-tls-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn error(settings: &TlsSettings) -> String {
+        client_config(settings).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn client_cert_without_key_is_rejected() {
+        let settings = TlsSettings {
+            enabled: true,
+            client_cert: Some(PathBuf::from("client.pem")),
+            insecure_skip_verify: true,
+            ..TlsSettings::default()
+        };
+        assert_eq!(
+            error(&settings),
+            "client_cert and client_key must be set together"
+        );
+    }
+
+    #[test]
+    fn invalid_verify_name_is_rejected() {
+        let settings = TlsSettings {
+            enabled: true,
+            verify_name: Some("not a host!".into()),
+            ..TlsSettings::default()
+        };
+        assert_eq!(error(&settings), "invalid TLS verify name 'not a host!'");
+    }
+
+    #[test]
+    fn ca_bundle_without_usable_certificates_is_rejected() {
+        let garbage = "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n";
+        let settings = TlsSettings {
+            enabled: true,
+            ca_file: Some(temp_file("garbage-ca.pem", garbage)),
+            ..TlsSettings::default()
+        };
+        assert_eq!(error(&settings), "no usable CA certificates found");
+
+        let settings = TlsSettings {
+            ca_file: Some(temp_file("empty-ca.pem", "")),
+            ..settings
+        };
+        assert!(error(&settings).ends_with("empty-ca.pem: no PEM certificates"));
+    }
+
+    #[test]
+    fn insecure_skip_verify_does_not_load_a_root_store() {
+        let settings = TlsSettings {
+            enabled: true,
+            ca_file: Some(PathBuf::from("/nonexistent/ca.pem")),
+            insecure_skip_verify: true,
+            ..TlsSettings::default()
+        };
+        assert!(client_config(&settings).is_ok());
+        let settings = TlsSettings {
+            insecure_skip_verify: false,
+            ..settings
+        };
+        assert!(error(&settings).starts_with("/nonexistent/ca.pem:"));
+    }
+}